@@ -3,10 +3,7 @@ use std::env;
 use std::fs::File;
 
 fn main() -> I2Result<()> {
-    let path = env::args()
-        .skip(1)
-        .next()
-        .unwrap_or("./samples/Sample1.ld".into());
+    let path = env::args().nth(1).unwrap_or("./samples/Sample1.ld".into());
     println!("Reading file: {}", path);
 
     let mut file = File::open(path).expect("Failed to open file!");
@@ -35,8 +32,7 @@ fn main() -> I2Result<()> {
     println!("Channle: {:#?}", channel);
 
     let data = reader.channel_data(channel)?;
-    for i in 0..6 {
-        let sample = &data[i];
+    for (i, sample) in data.iter().take(6).enumerate() {
         let value = sample.decode_f64(channel);
         println!("[{}]: {:.1} - (Raw Sample: {:?})", i, value, sample);
     }
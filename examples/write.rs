@@ -7,22 +7,18 @@ fn main() -> I2Result<()> {
 
     let mut file = File::create(filename).expect("Failed to open file!");
 
-    let header = Header {
-        channel_meta_ptr: 13384,
-        channel_data_ptr: 23056,
-        event_ptr: 1762,
-        device_serial: 12007,
-        device_type: "ADL".to_string(),
-        device_version: 420,
-        num_channels: 1,
-        date_string: "23/11/2005".to_string(),
-        time_string: "09:53:00".to_string(),
-        driver: "".to_string(),
-        vehicleid: "11A".to_string(),
-        venue: "Calder".to_string(),
-        session: "2".to_string(),
-        short_comment: "second warmup".to_string(),
-    };
+    let header = Header::builder()
+        .device_serial(12007)
+        .device_type("ADL")
+        .device_version(420)
+        .device_flags(0x0080)
+        .date("23/11/2005")
+        .time("09:53:00")
+        .vehicleid("11A")
+        .venue("Calder")
+        .session("2")
+        .short_comment("second warmup")
+        .build();
 
     let channel0_meta = ChannelMetadata {
         prev_addr: 0,
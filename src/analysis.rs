@@ -0,0 +1,416 @@
+use crate::{
+    ChannelMetadata, DecodedChannel, I2Result, Interpolation, LDReader, MetadataIndex, Sample,
+};
+use std::cmp::Ordering;
+use std::io::{Read, Seek};
+
+/// Compares two channels on a common time base
+///
+/// Returns `(time, value_a, value_b)` tuples using the timestamps of the channel with the
+/// highest sample rate. The other channel contributes its nearest sample, and points where that
+/// sample is more than `tolerance` seconds away are skipped. Both channels are assumed to start
+/// at time 0, and only the duration covered by the shorter channel is compared.
+pub fn compare_channels(
+    a: &DecodedChannel,
+    b: &DecodedChannel,
+    tolerance: f64,
+) -> Vec<(f64, f64, f64)> {
+    let duration = a.duration_secs().min(b.duration_secs());
+    let base = if a.sample_rate >= b.sample_rate { a } else { b };
+
+    base.times
+        .iter()
+        .take_while(|t| **t < duration)
+        .filter_map(|t| {
+            let value_a = nearest_value(a, *t, tolerance)?;
+            let value_b = nearest_value(b, *t, tolerance)?;
+            Some((*t, value_a, value_b))
+        })
+        .collect()
+}
+
+/// Returns the value of the sample nearest to `time`, if it is within `tolerance` seconds
+fn nearest_value(channel: &DecodedChannel, time: f64, tolerance: f64) -> Option<f64> {
+    let index = (time * channel.sample_rate as f64).round() as usize;
+    let sample_time = channel.times.get(index)?;
+    if (sample_time - time).abs() > tolerance {
+        return None;
+    }
+    channel.values.get(index).copied()
+}
+
+/// Resamples two channels onto the timestamps of the one with the highest sample rate
+///
+/// Returns `(time, value_a, value_b)` tuples with the decoded values of both channels. The
+/// channel with the lower sample rate is resampled with `interpolation`. Both channels are
+/// assumed to start at time 0, and only the duration covered by the shorter channel is aligned.
+pub fn align_channels(
+    a: &(ChannelMetadata, Vec<Sample>),
+    b: &(ChannelMetadata, Vec<Sample>),
+    interpolation: Interpolation,
+) -> Vec<(f64, f64, f64)> {
+    let decode = |(channel, samples): &(ChannelMetadata, Vec<Sample>)| -> Vec<f64> {
+        samples.iter().map(|s| s.decode_f64(channel)).collect()
+    };
+    let (values_a, values_b) = (decode(a), decode(b));
+    let (rate_a, rate_b) = (a.0.sample_rate as f64, b.0.sample_rate as f64);
+    if rate_a == 0.0 || rate_b == 0.0 {
+        return Vec::new();
+    }
+
+    let duration = (values_a.len() as f64 / rate_a).min(values_b.len() as f64 / rate_b);
+    let rate = rate_a.max(rate_b);
+    (0..)
+        .map(|i| i as f64 / rate)
+        .take_while(|t| *t < duration)
+        .map(|t| {
+            let value_a = interpolate(&values_a, t * rate_a, interpolation);
+            let value_b = interpolate(&values_b, t * rate_b, interpolation);
+            (t, value_a, value_b)
+        })
+        .collect()
+}
+
+/// Resamples the decoded values of a channel to `target_hz`, interpolating linearly between
+/// samples
+///
+/// The new samples are at `i / target_hz` seconds, covering the same duration as the channel's
+/// samples. Past the last sample its value is held. Returns no values if either rate is 0.
+pub fn resample(samples: &[Sample], channel: &ChannelMetadata, target_hz: u16) -> Vec<f64> {
+    let values: Vec<f64> = samples.iter().map(|s| s.decode_f64(channel)).collect();
+    let rate = channel.sample_rate as f64;
+    if rate == 0.0 || target_hz == 0 {
+        return Vec::new();
+    }
+
+    let duration = values.len() as f64 / rate;
+    (0..)
+        .map(|i| i as f64 / target_hz as f64)
+        .take_while(|t| *t < duration)
+        .map(|t| interpolate(&values, t * rate, Interpolation::Linear))
+        .collect()
+}
+
+/// Returns the value at the fractional sample index `position`, which must be in range
+fn interpolate(values: &[f64], position: f64, interpolation: Interpolation) -> f64 {
+    let index = position.floor() as usize;
+    let value = values[index];
+    match (interpolation, values.get(index + 1)) {
+        (Interpolation::Linear, Some(next)) => value + (next - value) * (position - index as f64),
+        _ => value,
+    }
+}
+
+/// Returns the sample at percentile `p` (0 to 100) using the nearest-rank method
+///
+/// Samples that can't be ordered (see [Sample]'s [PartialOrd] impl) sort as equal to each other.
+/// Returns `None` if there are no samples.
+pub fn percentile(samples: &[Sample], p: f64) -> Option<Sample> {
+    if samples.is_empty() {
+        return None;
+    }
+
+    let mut sorted = samples.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+
+    let rank = (p.clamp(0.0, 100.0) / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted.get(rank.saturating_sub(1)).cloned()
+}
+
+/// Converts `value` from unit `from` to unit `to`
+///
+/// Only common units for speed, temperature, pressure, distance and angles are known, returns
+/// `None` if either unit is unknown or they measure different things.
+pub fn convert_unit(value: f64, from: &str, to: &str) -> Option<f64> {
+    let (scale, offset) = unit_conversion(from, to)?;
+    Some(value * scale + offset)
+}
+
+/// Returns the scale and offset that convert a value from unit `from` to unit `to`, as in
+/// `value * scale + offset`, see [convert_unit]
+pub(crate) fn unit_conversion(from: &str, to: &str) -> Option<(f64, f64)> {
+    if from == to {
+        return Some((1.0, 0.0));
+    }
+
+    let (from_kind, from_factor, from_offset) = unit_definition(from)?;
+    let (to_kind, to_factor, to_offset) = unit_definition(to)?;
+    if from_kind != to_kind {
+        return None;
+    }
+
+    Some((
+        from_factor / to_factor,
+        (from_offset - to_offset) / to_factor,
+    ))
+}
+
+/// Returns the kind of a unit and the factor and offset that convert it into the base unit for
+/// that kind
+fn unit_definition(unit: &str) -> Option<(&'static str, f64, f64)> {
+    Some(match unit {
+        // Speed, base m/s
+        "m/s" => ("speed", 1.0, 0.0),
+        "km/h" | "kph" => ("speed", 1.0 / 3.6, 0.0),
+        "mph" => ("speed", 0.44704, 0.0),
+
+        // Temperature, base K
+        "K" => ("temperature", 1.0, 0.0),
+        "C" | "°C" => ("temperature", 1.0, 273.15),
+        "F" | "°F" => ("temperature", 5.0 / 9.0, 273.15 - 32.0 * 5.0 / 9.0),
+
+        // Pressure, base kPa
+        "kPa" => ("pressure", 1.0, 0.0),
+        "bar" => ("pressure", 100.0, 0.0),
+        "psi" => ("pressure", 6.894757, 0.0),
+
+        // Distance, base m
+        "m" => ("distance", 1.0, 0.0),
+        "mm" => ("distance", 0.001, 0.0),
+        "km" => ("distance", 1000.0, 0.0),
+        "ft" => ("distance", 0.3048, 0.0),
+        "mi" => ("distance", 1609.344, 0.0),
+
+        // Angle, base rad
+        "rad" => ("angle", 1.0, 0.0),
+        "deg" | "°" => ("angle", std::f64::consts::PI / 180.0, 0.0),
+        _ => return None,
+    })
+}
+
+/// Collects the distinct drivers, venues and vehicles of many files
+///
+/// Only the header of each file is read. Empty values are skipped.
+pub fn aggregate_metadata<'a, S: Read + Seek + 'a>(
+    readers: impl Iterator<Item = LDReader<'a, S>>,
+) -> I2Result<MetadataIndex> {
+    let mut index = MetadataIndex::default();
+    for mut reader in readers {
+        let header = reader.read_header()?;
+        for (set, value) in [
+            (&mut index.drivers, header.driver),
+            (&mut index.venues, header.venue),
+            (&mut index.vehicles, header.vehicleid),
+        ] {
+            if !value.is_empty() {
+                set.insert(value);
+            }
+        }
+    }
+    Ok(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        aggregate_metadata, align_channels, compare_channels, convert_unit, percentile, resample,
+        ChannelMetadata, Datatype, Interpolation, LDReader, LDWriter, Sample,
+    };
+    use std::fs;
+    use std::io::Cursor;
+
+    #[test]
+    fn compare_shifted_channel() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+
+        let channels = reader.read_channels().unwrap();
+        let a = reader.decode_channel(&channels[0]).unwrap();
+
+        // Shift the channel one sample earlier
+        let mut b = a.clone();
+        b.values.remove(0);
+        b.times.pop();
+
+        let compared = compare_channels(&a, &b, 0.01);
+        assert_eq!(compared.len(), b.values.len());
+        for (i, (time, value_a, value_b)) in compared.iter().enumerate() {
+            assert_eq!(*time, a.times[i]);
+            assert_eq!(*value_a, a.values[i]);
+            assert_eq!(*value_b, a.values[i + 1]);
+        }
+    }
+
+    #[test]
+    fn sample1_median() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+
+        let channels = reader.read_channels().unwrap();
+        let data = reader.channel_data(&channels[0]).unwrap();
+
+        let mut raw: Vec<i16> = data
+            .iter()
+            .map(|s| match s {
+                Sample::I16(v) => *v,
+                _ => unreachable!(),
+            })
+            .collect();
+        raw.sort();
+
+        let median = percentile(&data, 50.0).unwrap();
+        assert_eq!(median, Sample::I16(raw[raw.len() / 2 - 1]));
+        assert_eq!(percentile(&data, 0.0).unwrap(), Sample::I16(raw[0]));
+        assert_eq!(
+            percentile(&data, 100.0).unwrap(),
+            Sample::I16(raw[raw.len() - 1])
+        );
+        assert_eq!(percentile(&[], 50.0), None);
+    }
+
+    #[test]
+    fn sample_ordering() {
+        assert!(Sample::I16(1) < Sample::I16(2));
+        assert!(Sample::F32(1.5) > Sample::F32(-1.5));
+        assert_eq!(Sample::I16(1).partial_cmp(&Sample::I32(1)), None);
+        assert_eq!(Sample::F32(f32::NAN).partial_cmp(&Sample::F32(1.0)), None);
+    }
+
+    #[test]
+    fn aggregate_two_files() {
+        let sample1 = fs::read("./samples/Sample1.ld").unwrap();
+        let mut header = LDReader::new(&mut Cursor::new(&sample1))
+            .read_header()
+            .unwrap();
+        header.venue = "Sandown".to_string();
+        header.driver = "J. Smith".to_string();
+
+        let mut other = Cursor::new(Vec::new());
+        LDWriter::new(&mut other, header).write().unwrap();
+
+        let mut sources = [Cursor::new(sample1), other];
+        let index = aggregate_metadata(sources.iter_mut().map(LDReader::new)).unwrap();
+
+        assert_eq!(
+            index.venues.iter().collect::<Vec<_>>(),
+            ["Calder", "Sandown"]
+        );
+        assert_eq!(index.drivers.iter().collect::<Vec<_>>(), ["J. Smith"]);
+        assert_eq!(index.vehicles.iter().collect::<Vec<_>>(), ["11A"]);
+    }
+
+    #[test]
+    fn align_sample1_channels() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+
+        let channels = reader.read_channels().unwrap();
+        let air_temp = (
+            channels[0].clone(),
+            reader.channel_data(&channels[0]).unwrap(),
+        );
+        let brake_temp = (
+            channels[1].clone(),
+            reader.channel_data(&channels[1]).unwrap(),
+        );
+        assert_eq!(air_temp.0.sample_rate, 2);
+        assert_eq!(brake_temp.0.sample_rate, 10);
+
+        let held = align_channels(&air_temp, &brake_temp, Interpolation::ZeroOrderHold);
+        // Both channels span 454 seconds, aligned at 10Hz
+        assert_eq!(held.len(), 4540);
+        for (i, (t, a, b)) in held.iter().enumerate().take(20) {
+            assert!((t - i as f64 / 10.0).abs() < 1e-9);
+            assert_eq!(*a, air_temp.1[i / 5].decode_f64(&air_temp.0));
+            assert_eq!(*b, brake_temp.1[i].decode_f64(&brake_temp.0));
+        }
+
+        // Air Temp goes from 19.9 to 20.1 between 0.5s and 1.0s
+        let linear = align_channels(&air_temp, &brake_temp, Interpolation::Linear);
+        assert_eq!(linear.len(), 4540);
+        assert!((held[7].1 - 19.9).abs() < 1e-9);
+        assert!((linear[7].1 - 19.98).abs() < 1e-9);
+        assert_eq!(linear[7].2, held[7].2);
+    }
+
+    fn ramp_channel(hz: u16) -> ChannelMetadata {
+        ChannelMetadata {
+            prev_addr: 0,
+            next_addr: 0,
+            data_addr: 0,
+            data_count: 0,
+            datatype: Datatype::I16,
+            sample_rate: hz,
+            offset: 0,
+            mul: 1,
+            scale: 1,
+            dec_places: 0,
+            name: "Ramp".to_string(),
+            short_name: "Ramp".to_string(),
+            unit: "".to_string(),
+        }
+    }
+
+    #[test]
+    fn resample_ramp() {
+        let channel = ramp_channel(2);
+        let samples = [0, 1, 2, 3].map(Sample::I16);
+
+        // The midpoints are interpolated, and the last value is held for its last half sample
+        assert_eq!(
+            resample(&samples, &channel, 4),
+            [0.0, 0.5, 1.0, 1.5, 2.0, 2.5, 3.0, 3.0]
+        );
+        assert_eq!(resample(&samples, &channel, 1), [0.0, 2.0]);
+        assert_eq!(resample(&samples, &channel, 2), [0.0, 1.0, 2.0, 3.0]);
+        assert_eq!(
+            resample(&samples, &channel, 3),
+            [0.0, 2.0 / 3.0, 4.0 / 3.0, 2.0, 8.0 / 3.0, 3.0]
+        );
+    }
+
+    #[test]
+    fn resample_degenerate() {
+        let channel = ramp_channel(2);
+        assert_eq!(resample(&[Sample::I16(5)], &channel, 4), [5.0, 5.0]);
+        assert_eq!(resample(&[Sample::I16(5)], &channel, 1), [5.0]);
+        assert_eq!(resample(&[], &channel, 4), Vec::<f64>::new());
+        assert_eq!(resample(&[Sample::I16(5)], &channel, 0), Vec::<f64>::new());
+        assert_eq!(
+            resample(&[Sample::I16(5)], &ramp_channel(0), 4),
+            Vec::<f64>::new()
+        );
+    }
+
+    #[test]
+    fn convert_temperature() {
+        assert!((convert_unit(100.0, "C", "F").unwrap() - 212.0).abs() < 1e-9);
+        assert!((convert_unit(32.0, "F", "K").unwrap() - 273.15).abs() < 1e-9);
+        assert_eq!(convert_unit(1.0, "bar", "km/h"), None);
+        assert_eq!(convert_unit(1.0, "rpm", "rpm"), Some(1.0));
+    }
+
+    #[test]
+    fn convert_other_units() {
+        assert!((convert_unit(100.0, "km/h", "mph").unwrap() - 62.137119).abs() < 1e-6);
+        assert!((convert_unit(100.0, "kPa", "psi").unwrap() - 14.503774).abs() < 1e-6);
+        assert!((convert_unit(180.0, "deg", "rad").unwrap() - std::f64::consts::PI).abs() < 1e-9);
+        assert_eq!(convert_unit(1.0, "deg", "C"), None);
+    }
+
+    #[test]
+    fn sample1_air_temp_in_fahrenheit() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+
+        let channels = reader.read_channels().unwrap();
+        let air_temp = &channels[0];
+        assert_eq!(air_temp.unit, "C");
+
+        let (scale, offset) = air_temp.convert_unit(&air_temp.unit, "F").unwrap();
+        assert!((scale - 1.8).abs() < 1e-9);
+        assert!((offset - 32.0).abs() < 1e-9);
+        assert_eq!(air_temp.convert_unit(&air_temp.unit, "km/h"), None);
+
+        let data = reader.channel_data(air_temp).unwrap();
+        // 19.9 C
+        assert!((data[0].decode_f64_as(air_temp, "F").unwrap() - 67.82).abs() < 1e-6);
+        // 20.1 C
+        assert!((data[2].decode_f64_as(air_temp, "F").unwrap() - 68.18).abs() < 1e-6);
+        assert_eq!(data[0].decode_f64_as(air_temp, "psi"), None);
+    }
+}
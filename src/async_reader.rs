@@ -0,0 +1,206 @@
+use crate::reader::{decode_samples, eof_context};
+use crate::{ChannelMetadata, Datatype, FileAddr, Header, I2Error, I2Result, LDReader, Sample};
+use std::collections::HashSet;
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+
+/// Size in bytes of the header at the start of the file
+const HEADER_SIZE: u64 = 0x6E2;
+
+/// An async version of [LDReader], for sources such as [tokio::fs::File] or object storage
+/// streams
+///
+/// Each structure is read into memory with a single async read, and then parsed with the same
+/// code as [LDReader], so both readers always agree on the contents of a file.
+#[derive(Debug)]
+pub struct AsyncLDReader<'a, S: AsyncRead + AsyncSeek + Unpin> {
+    source: &'a mut S,
+    header: Option<Header>,
+}
+
+impl<'a, S: AsyncRead + AsyncSeek + Unpin> AsyncLDReader<'a, S> {
+    pub fn new(source: &'a mut S) -> Self {
+        Self {
+            source,
+            header: None,
+        }
+    }
+
+    /// Reads the header at the start of the file, see [LDReader::read_header]
+    pub async fn read_header(&mut self) -> I2Result<Header> {
+        let mut window = self.read_window(0, HEADER_SIZE).await?;
+        let header = LDReader::new(&mut window).read_header()?;
+        self.header = Some(header.clone());
+        Ok(header)
+    }
+
+    /// Reads the channel metadata list, see [LDReader::read_channels]
+    ///
+    /// Calls [AsyncLDReader::read_header] if it hasn't been called before.
+    pub async fn read_channels(&mut self) -> I2Result<Vec<ChannelMetadata>> {
+        if self.header.is_none() {
+            self.read_header().await?;
+        }
+
+        let mut channels = Vec::new();
+        let mut visited = HashSet::new();
+        let mut trailer_size = ChannelMetadata::TRAILER_SIZE;
+        let mut next_ptr = FileAddr::from(self.header.as_ref().unwrap().channel_meta_ptr);
+        while !next_ptr.is_zero() {
+            if !visited.insert(next_ptr) {
+                return Err(I2Error::ChannelListCycle { at: next_ptr.0 });
+            }
+
+            // The largest entry that we accept, the parser only reads what it needs of it
+            let max_size = ChannelMetadata::FIXED_SIZE + ChannelMetadata::MAX_TRAILER_SIZE;
+            let mut window = self.read_window(next_ptr.into(), max_size as u64).await?;
+            let (channel, trailer) =
+                LDReader::new(&mut window).read_channel_metadata(next_ptr, trailer_size)?;
+
+            trailer_size = trailer;
+            next_ptr = FileAddr::from(channel.next_addr);
+            channels.push(channel);
+        }
+        Ok(channels)
+    }
+
+    /// Reads all samples of a channel, see [LDReader::channel_data]
+    pub async fn channel_data(&mut self, channel: &ChannelMetadata) -> I2Result<Vec<Sample>> {
+        let header_has_data = self.header.as_ref().is_none_or(|h| h.channel_data_ptr != 0);
+        if !header_has_data || channel.data_addr == 0 {
+            return Ok(Vec::new());
+        }
+        if channel.datatype == Datatype::Invalid {
+            if channel.data_count == 0 {
+                return Ok(Vec::new());
+            }
+            return Err(I2Error::InvalidChannelData {
+                name: channel.name.clone(),
+            });
+        }
+
+        let size = channel.data_size() as u64;
+        let window = self.read_window(channel.data_addr as u64, size).await?;
+        let bytes = window.cursor.into_inner();
+        if (bytes.len() as u64) < size {
+            let eof = io::Error::from(io::ErrorKind::UnexpectedEof);
+            return Err(eof_context("channel data", channel.data_addr as u64)(
+                eof.into(),
+            ));
+        }
+
+        let mut data = Vec::new();
+        decode_samples(&bytes, channel, &mut data);
+        Ok(data)
+    }
+
+    /// Reads up to `len` bytes at `addr`, fewer if the file ends before that
+    async fn read_window(&mut self, addr: u64, len: u64) -> I2Result<Window> {
+        self.source.seek(SeekFrom::Start(addr)).await?;
+        let mut bytes = Vec::with_capacity(len as usize);
+        (&mut *self.source)
+            .take(len)
+            .read_to_end(&mut bytes)
+            .await?;
+        Ok(Window {
+            start: addr,
+            cursor: Cursor::new(bytes),
+        })
+    }
+}
+
+/// A region of a file held in memory, that is read with the same offsets as the whole file
+///
+/// Reading past the end of the region behaves as the end of the file.
+struct Window {
+    start: u64,
+    cursor: Cursor<Vec<u8>>,
+}
+
+impl Read for Window {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        Read::read(&mut self.cursor, buf)
+    }
+}
+
+impl Seek for Window {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let pos = match pos {
+            SeekFrom::Start(addr) => {
+                let offset = addr.checked_sub(self.start).ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidInput, "seek before the window")
+                })?;
+                SeekFrom::Start(offset)
+            }
+            pos => pos,
+        };
+        Ok(self.start + Seek::seek(&mut self.cursor, pos)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{AsyncLDReader, I2Error, LDReader};
+    use std::fs;
+    use std::io::Cursor;
+
+    #[tokio::test]
+    async fn read_sample1_async() {
+        let mut file = tokio::fs::File::open("./samples/Sample1.ld").await.unwrap();
+        let mut reader = AsyncLDReader::new(&mut file);
+
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut cursor = Cursor::new(bytes);
+        let mut sync_reader = LDReader::new(&mut cursor);
+
+        let header = reader.read_header().await.unwrap();
+        assert_eq!(header, sync_reader.read_header().unwrap());
+        assert_eq!(header.venue, "Calder");
+
+        let channels = reader.read_channels().await.unwrap();
+        assert_eq!(channels, sync_reader.read_channels().unwrap());
+        assert_eq!(channels.len(), 78);
+
+        for channel in channels.iter() {
+            assert_eq!(
+                reader.channel_data(channel).await.unwrap(),
+                sync_reader.channel_data(channel).unwrap()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn read_truncated_async() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+
+        let mut header = Cursor::new(bytes[..1000].to_vec());
+        assert!(matches!(
+            AsyncLDReader::new(&mut header).read_header().await,
+            Err(I2Error::UnexpectedEof {
+                while_reading: "header",
+                offset: 0
+            })
+        ));
+
+        let mut metadata = Cursor::new(bytes[..0x3448 + 100].to_vec());
+        assert!(matches!(
+            AsyncLDReader::new(&mut metadata).read_channels().await,
+            Err(I2Error::UnexpectedEof {
+                while_reading: "channel metadata",
+                offset: 0x3448
+            })
+        ));
+
+        let mut data = Cursor::new(bytes[..bytes.len() - 1].to_vec());
+        let mut reader = AsyncLDReader::new(&mut data);
+        let channels = reader.read_channels().await.unwrap();
+        let last = channels.last().unwrap();
+        assert!(matches!(
+            reader.channel_data(last).await,
+            Err(I2Error::UnexpectedEof {
+                while_reading: "channel data",
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,81 @@
+use crate::export::Resampled;
+use crate::{I2Result, LDReader};
+use polars::prelude::{Column, DataFrame, NamedFrom, Series};
+use std::io::{Read, Seek};
+
+impl<S: Read + Seek> LDReader<'_, S> {
+    /// Reads every channel into a [DataFrame] with one column of decoded values per channel
+    ///
+    /// Columns are named by the channel's name, and there is one row per sample of the channel
+    /// with the highest sample rate. Channels with a lower sample rate are resampled by
+    /// repeating each value until their next sample, and are padded with nulls after their last
+    /// one, so that all columns have the same length. Channels with a [crate::Datatype::Invalid]
+    /// or a 0 sample rate are skipped.
+    pub fn to_dataframe(&mut self) -> I2Result<DataFrame> {
+        let resampled = Resampled::read(self)?;
+
+        let columns = resampled
+            .channels
+            .iter()
+            .enumerate()
+            .map(|(i, channel)| {
+                let values: Vec<Option<f64>> = (0..resampled.rows)
+                    .map(|row| resampled.value(i, row))
+                    .collect();
+                Column::from(Series::new(channel.name.as_str().into(), values))
+            })
+            .collect();
+
+        Ok(DataFrame::new(columns)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{LDReader, LDWriter};
+    use std::fs;
+    use std::io::Cursor;
+
+    #[test]
+    fn sample1_dataframe() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+
+        let df = reader.to_dataframe().unwrap();
+        // 454 seconds at 100Hz, the rate of the Susp Pos channels
+        assert_eq!(df.shape(), (45400, 78));
+
+        let names = df.get_column_names();
+        assert_eq!(names[0].as_str(), "Air Temp Inlet");
+        assert_eq!(names[1].as_str(), "Brake Temp FL");
+        assert_eq!(names[77].as_str(), "Steered Angle");
+
+        // Air Temp Inlet is logged at 2Hz, so each value is repeated for 50 rows
+        let air_temp = df.column("Air Temp Inlet").unwrap().f64().unwrap();
+        assert!((air_temp.get(0).unwrap() - 19.9).abs() < 0.000001);
+        assert!((air_temp.get(49).unwrap() - 19.9).abs() < 0.000001);
+        assert!((air_temp.get(100).unwrap() - 20.1).abs() < 0.000001);
+        assert_eq!(air_temp.null_count(), 0);
+    }
+
+    #[test]
+    fn dataframe_pads_short_channels() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let header = LDReader::new(&mut Cursor::new(bytes))
+            .read_header()
+            .unwrap();
+
+        let mut cursor = Cursor::new(Vec::new());
+        let mut writer = LDWriter::new(&mut cursor, header);
+        writer.log("Lap", "", 1, &[1.0]).unwrap();
+        writer.log("Speed", "km/h", 2, &[10.0, 20.0, 30.0]).unwrap();
+
+        let mut reader = LDReader::new(&mut cursor);
+        let df = reader.to_dataframe().unwrap();
+        let lap: Vec<_> = df.column("Lap").unwrap().f64().unwrap().iter().collect();
+        let speed: Vec<_> = df.column("Speed").unwrap().f64().unwrap().iter().collect();
+        assert_eq!(lap, [Some(1.0), Some(1.0), None]);
+        assert_eq!(speed, [Some(10.0), Some(20.0), Some(30.0)]);
+    }
+}
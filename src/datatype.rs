@@ -0,0 +1,120 @@
+use crate::{I2Error, I2Result};
+
+#[derive(Debug, Clone, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Datatype {
+    // TODO: Not Too sure about this data type, it shows up as beacon in the sample dataset
+    // It behaves as an integer of the same size
+    Beacon16,
+    Beacon32,
+
+    I16,
+    I32,
+
+    F16,
+    F32,
+
+    Invalid,
+}
+
+impl Datatype {
+    /// Size in bytes that this datatype occupies on file
+    pub fn size(&self) -> u16 {
+        match self {
+            Datatype::Beacon16 | Datatype::I16 | Datatype::F16 => 2,
+            Datatype::Beacon32 | Datatype::I32 | Datatype::F32 => 4,
+
+            // We really don't know what these values are
+            Datatype::Invalid => 0,
+        }
+    }
+
+    pub fn _type(&self) -> u16 {
+        match self {
+            Datatype::Beacon16 | Datatype::Beacon32 => 0,
+            Datatype::I16 | Datatype::I32 => 3,
+            Datatype::F16 | Datatype::F32 => 7,
+            Datatype::Invalid => 999,
+        }
+    }
+
+    pub fn from_type_and_size(_type: u16, size: u16) -> I2Result<Self> {
+        match (_type, size) {
+            (0, 2) => Ok(Datatype::Beacon16),
+            (0, 4) => Ok(Datatype::Beacon32),
+            (3, 2) => Ok(Datatype::I16),
+            (3, 4) => Ok(Datatype::I32),
+            // 20160903-0051401.ld uses 5 for ints?
+            (5, 2) => Ok(Datatype::I16),
+            (5, 4) => Ok(Datatype::I32),
+            (7, 2) => Ok(Datatype::F16),
+            (7, 4) => Ok(Datatype::F32),
+
+            // The mu iracing exporter exports these values on Damper Pos FL/FR/RL, they have 0 samples
+            (17536, 5) | (6566, 5) | (29813, 5) => Ok(Datatype::Invalid),
+            // This should be Beacon40 ?, but the iRacing mu exporter puts this in Damper Pos RR
+            (0, 5) => Ok(Datatype::Invalid),
+            // Iracing mu exporter Ride Height Center 0 samples
+            (15, 5) => Ok(Datatype::Invalid),
+            _ => Err(I2Error::UnrecognizedDatatype { _type, size }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Datatype, I2Error};
+
+    #[test]
+    fn datatype_round_trip() {
+        let round_trip = [
+            (Datatype::Beacon16, 0, 2),
+            (Datatype::Beacon32, 0, 4),
+            (Datatype::I16, 3, 2),
+            (Datatype::I32, 3, 4),
+            (Datatype::F16, 7, 2),
+            (Datatype::F32, 7, 4),
+        ];
+        for (datatype, _type, size) in round_trip {
+            assert_eq!((datatype._type(), datatype.size()), (_type, size));
+            assert_eq!(Datatype::from_type_and_size(_type, size).unwrap(), datatype);
+        }
+    }
+
+    #[test]
+    fn datatype_lossy_mappings() {
+        // Some files use 5 as the type of ints, we read them as ints but always write 3
+        let lossy = [
+            ((5, 2), Datatype::I16, (3, 2)),
+            ((5, 4), Datatype::I32, (3, 4)),
+        ];
+        for (read, datatype, written) in lossy {
+            assert_eq!(
+                Datatype::from_type_and_size(read.0, read.1).unwrap(),
+                datatype
+            );
+            assert_eq!((datatype._type(), datatype.size()), written);
+        }
+
+        // Channels without samples in files exported from iRacing have these, there is no way to
+        // know what they were so they are all read as Invalid
+        let invalid = [(17536, 5), (6566, 5), (29813, 5), (0, 5), (15, 5)];
+        for (_type, size) in invalid {
+            assert_eq!(
+                Datatype::from_type_and_size(_type, size).unwrap(),
+                Datatype::Invalid
+            );
+        }
+
+        // Invalid is written as a type and size that we don't recognize when reading
+        let invalid = Datatype::Invalid;
+        assert_eq!((invalid._type(), invalid.size()), (999, 0));
+        assert!(matches!(
+            Datatype::from_type_and_size(999, 0),
+            Err(I2Error::UnrecognizedDatatype {
+                _type: 999,
+                size: 0
+            })
+        ));
+    }
+}
@@ -1,24 +1,106 @@
-use std::error::Error;
-use std::fmt;
+use crate::Datatype;
+use alloc::string::String;
+use core::error::Error;
+use core::fmt;
+use core::str::Utf8Error;
+#[cfg(feature = "std")]
 use std::io;
-use std::str::Utf8Error;
 
 pub type I2Result<T> = Result<T, I2Error>;
 
 #[derive(Debug)]
 pub enum I2Error {
+    #[cfg(feature = "std")]
     IOError(io::Error),
+    #[cfg(feature = "hound")]
+    WavError(hound::Error),
+    #[cfg(feature = "polars")]
+    PolarsError(polars::error::PolarsError),
+    #[cfg(feature = "serde")]
+    JsonError(serde_json::Error),
 
     // Parsing Errors
-    InvalidHeaderMarker { found: u32, expected: u32 },
-    UnrecognizedDatatype { _type: u16, size: u16 },
+    InvalidHeaderMarker {
+        found: u32,
+        expected: u32,
+    },
+    UnrecognizedDatatype {
+        _type: u16,
+        size: u16,
+    },
     NonUtf8String(Utf8Error),
+    InvalidLdx(String),
+    InvalidCsv {
+        line: usize,
+        message: String,
+    },
+    ChannelListCycle {
+        at: u32,
+    },
+    ChannelCountMismatch {
+        header: u32,
+        found: u32,
+    },
+    InvalidChannelData {
+        name: String,
+    },
+    UnexpectedEof {
+        while_reading: &'static str,
+        offset: u64,
+    },
+    SampleIndexOutOfRange {
+        channel: String,
+        index: u32,
+        data_count: u32,
+    },
+
+    // Writing Errors
+    SampleOverflow {
+        value: f64,
+        datatype: Datatype,
+    },
+    UnsupportedDatatype {
+        datatype: Datatype,
+    },
+    SampleDatatypeMismatch {
+        channel: String,
+        expected: Datatype,
+        found: Datatype,
+    },
+    PaddingTooSmall {
+        size: u64,
+        content_size: u64,
+    },
+    StreamCapacityExceeded {
+        channel: String,
+        capacity: u32,
+    },
+    UnknownStreamChannel {
+        id: usize,
+    },
+    StreamAddressOverflow {
+        channel: String,
+        max_duration_secs: u32,
+    },
+
+    // Conversion Errors
+    UnknownUnitConversion {
+        from: String,
+        to: String,
+    },
 }
 
 impl fmt::Display for I2Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            #[cfg(feature = "std")]
             I2Error::IOError(e) => write!(f, "Underlying IO Error: {}", e),
+            #[cfg(feature = "hound")]
+            I2Error::WavError(e) => write!(f, "Failed to write WAV file: {}", e),
+            #[cfg(feature = "polars")]
+            I2Error::PolarsError(e) => write!(f, "Failed to build DataFrame: {}", e),
+            #[cfg(feature = "serde")]
+            I2Error::JsonError(e) => write!(f, "Failed to serialize JSON: {}", e),
             I2Error::InvalidHeaderMarker { found, expected } => write!(
                 f,
                 "Invalid Header Marker found {}, expected {}",
@@ -30,12 +112,93 @@ impl fmt::Display for I2Error {
                 _type, size
             ),
             I2Error::NonUtf8String(e) => write!(f, "Attempted to decode non utf8 string: {}", e),
+            I2Error::InvalidLdx(e) => write!(f, "Invalid ldx file: {}", e),
+            I2Error::InvalidCsv { line, message } => {
+                write!(f, "Invalid CSV on line {}: {}", line, message)
+            }
+            I2Error::ChannelListCycle { at } => {
+                write!(f, "Channel metadata list loops back to address {}", at)
+            }
+            I2Error::ChannelCountMismatch { header, found } => write!(
+                f,
+                "Header says there are {} channels, but {} were found",
+                header, found
+            ),
+            I2Error::InvalidChannelData { name } => write!(
+                f,
+                "Tried to read samples of invalid datatype from channel: {}",
+                name
+            ),
+            I2Error::UnexpectedEof {
+                while_reading,
+                offset,
+            } => write!(
+                f,
+                "File ended while reading the {} starting at offset {}",
+                while_reading, offset
+            ),
+            I2Error::SampleIndexOutOfRange {
+                channel,
+                index,
+                data_count,
+            } => write!(
+                f,
+                "Sample {} is out of range for channel {} with {} samples",
+                index, channel, data_count
+            ),
+            I2Error::SampleOverflow { value, datatype } => write!(
+                f,
+                "Value {} does not fit in a sample of datatype {:?}",
+                value, datatype
+            ),
+            I2Error::UnsupportedDatatype { datatype } => {
+                write!(
+                    f,
+                    "Encoding samples of datatype {:?} is not supported",
+                    datatype
+                )
+            }
+            I2Error::SampleDatatypeMismatch {
+                channel,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Channel {:?} has datatype {:?}, but was given a {:?} sample",
+                channel, expected, found
+            ),
+            I2Error::PaddingTooSmall { size, content_size } => write!(
+                f,
+                "Can't pad file to {} bytes, the content is already {} bytes",
+                size, content_size
+            ),
+            I2Error::StreamCapacityExceeded { channel, capacity } => write!(
+                f,
+                "Channel {:?} can't hold more than the {} samples reserved for it",
+                channel, capacity
+            ),
+            I2Error::UnknownStreamChannel { id } => {
+                write!(f, "No channel with id {} was registered in the stream", id)
+            }
+            I2Error::StreamAddressOverflow {
+                channel,
+                max_duration_secs,
+            } => write!(
+                f,
+                "Reserving {} seconds of samples for channel {:?} goes past the end of the \
+                 largest ld file",
+                max_duration_secs, channel
+            ),
+            I2Error::UnknownUnitConversion { from, to } => {
+                write!(f, "Don't know how to convert from {:?} to {:?}", from, to)
+            }
         }
     }
 }
 
 impl Error for I2Error {}
 
+#[cfg(feature = "std")]
 impl From<io::Error> for I2Error {
     fn from(e: io::Error) -> Self {
         I2Error::IOError(e)
@@ -47,3 +210,24 @@ impl From<Utf8Error> for I2Error {
         I2Error::NonUtf8String(e)
     }
 }
+
+#[cfg(feature = "hound")]
+impl From<hound::Error> for I2Error {
+    fn from(e: hound::Error) -> Self {
+        I2Error::WavError(e)
+    }
+}
+
+#[cfg(feature = "polars")]
+impl From<polars::error::PolarsError> for I2Error {
+    fn from(e: polars::error::PolarsError) -> Self {
+        I2Error::PolarsError(e)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<serde_json::Error> for I2Error {
+    fn from(e: serde_json::Error) -> Self {
+        I2Error::JsonError(e)
+    }
+}
@@ -0,0 +1,319 @@
+use crate::{Datatype, DecodedChannel, I2Result, LDReader};
+use byteorder::{LittleEndian, WriteBytesExt};
+use std::collections::HashSet;
+use std::io::{Read, Seek, Write};
+
+/// Exports every channel of a file as CSV, with one column per channel
+///
+/// The first column is the time in seconds, with one row per sample of the channel with the
+/// highest sample rate. Channels with a lower sample rate repeat each value until their next
+/// sample, and are left blank after their last one. Values are decoded with
+/// [crate::Sample::decode_f64], and the header row uses the channel's name and unit.
+pub fn export_csv<S: Read + Seek, W: Write>(reader: &mut LDReader<S>, out: &mut W) -> I2Result<()> {
+    let resampled = Resampled::read(reader)?;
+
+    write!(out, "Time [s]")?;
+    for channel in resampled.channels.iter() {
+        write!(
+            out,
+            ",{}",
+            escape(&format!("{} [{}]", channel.name, channel.unit))
+        )?;
+    }
+    writeln!(out)?;
+
+    for row in 0..resampled.rows {
+        write!(out, "{}", resampled.time(row))?;
+        for channel in 0..resampled.channels.len() {
+            match resampled.value(channel, row) {
+                Some(value) => write!(out, ",{}", value)?,
+                None => write!(out, ",")?,
+            }
+        }
+        writeln!(out)?;
+    }
+
+    Ok(())
+}
+
+/// Exports every channel of a file as a MATLAB v5 .mat file
+///
+/// Each channel is stored as a column vector of doubles with its decoded values, named after the
+/// channel. Names are turned into valid MATLAB identifiers by replacing other characters with
+/// `_`, and a numeric suffix is added if that makes two of them equal. Channels with a
+/// [Datatype::Invalid] are skipped.
+pub fn export_mat<S: Read + Seek, W: Write>(reader: &mut LDReader<S>, out: &mut W) -> I2Result<()> {
+    let channels: Vec<DecodedChannel> = reader
+        .read_channels()?
+        .iter()
+        .filter(|c| c.datatype != Datatype::Invalid)
+        .map(|c| reader.decode_channel(c))
+        .collect::<I2Result<_>>()?;
+
+    let mut text = format!(
+        "MATLAB 5.0 MAT-file, Platform: {}, Created by: motec-i2",
+        std::env::consts::OS
+    )
+    .into_bytes();
+    text.resize(MAT_TEXT_SIZE, b' ');
+    out.write_all(&text)?;
+    // No subsystem data
+    out.write_all(&[0u8; 8])?;
+    out.write_u16::<LittleEndian>(0x0100)?;
+    // Written as "IM" when the file is little endian
+    out.write_u16::<LittleEndian>(u16::from_be_bytes(*b"MI"))?;
+
+    let mut names = HashSet::new();
+    for channel in channels.iter() {
+        let name = mat_name(&channel.name, &mut names);
+        write_mat_vector(out, &name, &channel.values)?;
+    }
+    Ok(())
+}
+
+/// Size of the descriptive text at the start of a .mat file
+const MAT_TEXT_SIZE: usize = 116;
+
+// Data types and array classes of the .mat format
+const MI_INT8: u32 = 1;
+const MI_INT32: u32 = 5;
+const MI_UINT32: u32 = 6;
+const MI_DOUBLE: u32 = 9;
+const MI_MATRIX: u32 = 14;
+const MX_DOUBLE_CLASS: u32 = 6;
+
+/// Turns `name` into a valid MATLAB variable name that isn't in `used`, and adds it there
+fn mat_name(name: &str, used: &mut HashSet<String>) -> String {
+    const MAX_LEN: usize = 63;
+
+    let mut base: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if !base.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        base.insert_str(0, "ch_");
+    }
+    base.truncate(MAX_LEN);
+
+    let mut name = base.clone();
+    let mut suffix = 1;
+    while used.contains(&name) {
+        suffix += 1;
+        let suffix = format!("_{}", suffix);
+        name = format!(
+            "{}{}",
+            &base[..base.len().min(MAX_LEN - suffix.len())],
+            suffix
+        );
+    }
+    used.insert(name.clone());
+    name
+}
+
+/// Writes a data element tag followed by `data`, padded to 8 bytes
+fn write_mat_element<W: Write>(out: &mut W, data_type: u32, data: &[u8]) -> I2Result<()> {
+    out.write_u32::<LittleEndian>(data_type)?;
+    out.write_u32::<LittleEndian>(data.len() as u32)?;
+    out.write_all(data)?;
+    out.write_all(&[0u8; 8][..padding(data.len())])?;
+    Ok(())
+}
+
+/// Writes `values` as a column vector of doubles named `name`
+fn write_mat_vector<W: Write>(out: &mut W, name: &str, values: &[f64]) -> I2Result<()> {
+    let mut matrix = Vec::new();
+
+    let mut flags = Vec::new();
+    flags.write_u32::<LittleEndian>(MX_DOUBLE_CLASS)?;
+    flags.write_u32::<LittleEndian>(0)?;
+    write_mat_element(&mut matrix, MI_UINT32, &flags)?;
+
+    let mut dimensions = Vec::new();
+    dimensions.write_i32::<LittleEndian>(values.len() as i32)?;
+    dimensions.write_i32::<LittleEndian>(1)?;
+    write_mat_element(&mut matrix, MI_INT32, &dimensions)?;
+
+    write_mat_element(&mut matrix, MI_INT8, name.as_bytes())?;
+
+    let mut real = Vec::with_capacity(values.len() * 8);
+    for value in values {
+        real.write_f64::<LittleEndian>(*value)?;
+    }
+    write_mat_element(&mut matrix, MI_DOUBLE, &real)?;
+
+    write_mat_element(out, MI_MATRIX, &matrix)
+}
+
+/// Bytes needed to pad `len` to a multiple of 8
+fn padding(len: usize) -> usize {
+    (8 - len % 8) % 8
+}
+
+/// The decoded channels of a file on a common time base, with one row per sample of the channel
+/// with the highest sample rate
+///
+/// Channels with a lower sample rate hold each value until their next sample, and have no value
+/// after their last one. Channels with a [Datatype::Invalid] or a 0 sample rate are skipped.
+#[derive(Debug, Clone)]
+pub(crate) struct Resampled {
+    pub(crate) channels: Vec<DecodedChannel>,
+    /// Sample rate of the rows, in Hz
+    pub(crate) rate: u64,
+    pub(crate) rows: u64,
+}
+
+impl Resampled {
+    pub(crate) fn read<S: Read + Seek>(reader: &mut LDReader<S>) -> I2Result<Self> {
+        let channels: Vec<DecodedChannel> = reader
+            .read_channels()?
+            .iter()
+            .filter(|c| c.datatype != Datatype::Invalid && c.sample_rate != 0)
+            .map(|c| reader.decode_channel(c))
+            .collect::<I2Result<_>>()?;
+
+        let rate = channels.iter().map(|c| c.sample_rate).max().unwrap_or(0) as u64;
+        let rows = channels
+            .iter()
+            .map(|c| (c.values.len() as u64 * rate).div_ceil(c.sample_rate as u64))
+            .max()
+            .unwrap_or(0);
+
+        Ok(Self {
+            channels,
+            rate,
+            rows,
+        })
+    }
+
+    /// Time in seconds of `row`
+    pub(crate) fn time(&self, row: u64) -> f64 {
+        row as f64 / self.rate as f64
+    }
+
+    /// Value of the channel at index `channel` in `row`, if it has one
+    pub(crate) fn value(&self, channel: usize, row: u64) -> Option<f64> {
+        let channel = &self.channels[channel];
+        let index = (row * channel.sample_rate as u64 / self.rate) as usize;
+        channel.values.get(index).copied()
+    }
+}
+
+/// Quotes a CSV field if needed
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::export::mat_name;
+    use crate::{export_csv, export_mat, LDReader, LDWriter};
+    use std::collections::HashSet;
+    use std::fs;
+    use std::io::Cursor;
+
+    #[test]
+    fn export_sample1() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+
+        let mut csv = Vec::new();
+        export_csv(&mut reader, &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let rows: Vec<Vec<&str>> = csv.lines().map(|l| l.split(',').collect()).collect();
+
+        let header = &rows[0];
+        assert_eq!(header.len(), 79);
+        assert_eq!(header[0], "Time [s]");
+        assert_eq!(header[1], "Air Temp Inlet [C]");
+        assert_eq!(header[78], "Steered Angle [deg]");
+
+        // 454 seconds at 100Hz, the rate of the Susp Pos channels
+        assert_eq!(rows.len(), 1 + 45400);
+
+        let column = |name: &str| header.iter().position(|h| *h == name).unwrap();
+        let air_temp = column("Air Temp Inlet [C]");
+        let brake_temp = column("Brake Temp FL [C]");
+
+        let parse = |row: usize, col: usize| rows[row][col].parse::<f64>().unwrap();
+        assert_eq!(parse(1, 0), 0.0);
+        assert_eq!(parse(2, 0), 0.01);
+
+        // Air Temp Inlet is logged at 2Hz, so each value is repeated for 50 rows
+        for row in 1..=50 {
+            assert!((parse(row, air_temp) - 19.9).abs() < 0.000001);
+        }
+        // Brake Temp FL is logged at 10Hz, so each value is repeated for 10 rows
+        for row in 2..=10 {
+            assert_eq!(parse(row, brake_temp), parse(1, brake_temp));
+        }
+    }
+
+    #[test]
+    fn export_sample1_mat() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+
+        let mut mat = Vec::new();
+        export_mat(&mut reader, &mut mat).unwrap();
+
+        assert!(mat.starts_with(b"MATLAB 5.0 MAT-file"));
+        assert_eq!(mat[124..126], [0x00, 0x01]);
+        assert_eq!(&mat[126..128], b"IM");
+
+        // Walk the top level data elements
+        let u32_at = |i: usize| u32::from_le_bytes(mat[i..i + 4].try_into().unwrap());
+        let mut offset = 128;
+        let mut variables = Vec::new();
+        while offset < mat.len() {
+            assert_eq!(u32_at(offset), 14);
+            let size = u32_at(offset + 4) as usize;
+            variables.push(offset + 8);
+            offset += 8 + size;
+        }
+        assert_eq!(offset, mat.len());
+        assert_eq!(variables.len(), 78);
+
+        // The array flags and dimensions take 16 bytes each, followed by the name
+        let first = variables[0];
+        assert_eq!(u32_at(first + 24), 908);
+        assert_eq!(u32_at(first + 32), 1);
+        let name_len = u32_at(first + 36) as usize;
+        assert_eq!(&mat[first + 40..first + 40 + name_len], b"Air_Temp_Inlet");
+    }
+
+    #[test]
+    fn mat_names() {
+        let mut used = HashSet::new();
+        assert_eq!(mat_name("Air Temp Inlet", &mut used), "Air_Temp_Inlet");
+        assert_eq!(mat_name("Air Temp-Inlet", &mut used), "Air_Temp_Inlet_2");
+        assert_eq!(mat_name("2nd Gear", &mut used), "ch_2nd_Gear");
+        assert_eq!(mat_name(&"x".repeat(80), &mut used).len(), 63);
+    }
+
+    #[test]
+    fn export_blanks_short_channels() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let header = LDReader::new(&mut Cursor::new(bytes))
+            .read_header()
+            .unwrap();
+
+        let mut cursor = Cursor::new(Vec::new());
+        let mut writer = LDWriter::new(&mut cursor, header);
+        writer.log("Lap, Number", "", 1, &[1.0]).unwrap();
+        writer.log("Speed", "km/h", 2, &[10.0, 20.0, 30.0]).unwrap();
+
+        let mut reader = LDReader::new(&mut cursor);
+        let mut csv = Vec::new();
+        export_csv(&mut reader, &mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "Time [s],\"Lap, Number []\",Speed [km/h]\n0,1,10\n0.5,1,20\n1,,30\n"
+        );
+    }
+}
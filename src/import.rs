@@ -0,0 +1,275 @@
+use crate::{ChannelMetadata, Datatype, I2Error, I2Result, LDWriter};
+use std::io::{BufRead, Seek, Write};
+
+/// Builds channels from CSV telemetry, the inverse of [crate::export_csv]
+///
+/// The first column is the time in seconds and every other column is a channel. Header fields
+/// of the form `Name [unit]` set the channel's unit, otherwise it is left empty. Each row must
+/// have the same number of fields as the header.
+///
+/// A field may be left blank when a channel has no sample at that time, and each channel's
+/// sample rate is inferred from the times of its non blank fields. Channels that end early are
+/// written with fewer samples. Channels whose values are all integers that fit in an i16 are
+/// stored as [Datatype::I16], the rest as [Datatype::F32].
+pub fn import_csv<R: BufRead, S: Write + Seek>(input: R, writer: &mut LDWriter<S>) -> I2Result<()> {
+    let mut lines = input.lines().enumerate();
+
+    let header = match lines.next() {
+        Some((_, line)) => split_fields(&line?, 1)?,
+        None => return Err(invalid_csv(1, "missing header row")),
+    };
+    if header.is_empty() || header[0].is_empty() {
+        return Err(invalid_csv(1, "missing time column"));
+    }
+    let mut columns: Vec<Column> = header[1..]
+        .iter()
+        .map(|field| Column::from_header(field))
+        .collect();
+
+    let mut times = Vec::new();
+    for (index, line) in lines {
+        let line_number = index + 1;
+        let line = line?;
+        if line.is_empty() {
+            continue;
+        }
+
+        let fields = split_fields(&line, line_number)?;
+        if fields.len() != header.len() {
+            return Err(invalid_csv(
+                line_number,
+                &format!(
+                    "expected {} fields like the header, found {}",
+                    header.len(),
+                    fields.len()
+                ),
+            ));
+        }
+
+        let time = parse_value(&fields[0], line_number)?
+            .ok_or_else(|| invalid_csv(line_number, "missing time"))?;
+        if times.last().is_some_and(|last| time <= *last) {
+            return Err(invalid_csv(line_number, "time is not increasing"));
+        }
+        times.push(time);
+
+        for (column, field) in columns.iter_mut().zip(&fields[1..]) {
+            if let Some(value) = parse_value(field, line_number)? {
+                column.times.push(time);
+                column.values.push(value);
+            }
+        }
+    }
+
+    let default_rate = infer_rate(&times).unwrap_or(1);
+    for column in columns {
+        let (datatype, dec_places) = pick_datatype(&column.values);
+        let channel = ChannelMetadata {
+            prev_addr: 0,
+            next_addr: 0,
+            data_addr: 0,
+            data_count: 0,
+            datatype,
+            sample_rate: infer_rate(&column.times).unwrap_or(default_rate),
+            offset: 0,
+            mul: 1,
+            scale: 1,
+            dec_places,
+            short_name: column.name.clone(),
+            name: column.name,
+            unit: column.unit,
+        };
+
+        let mut handle = writer.begin_channel(channel)?;
+        handle.write_block_f64(&column.values)?;
+        handle.finish()?;
+    }
+
+    Ok(())
+}
+
+/// A channel being read from a CSV column
+struct Column {
+    name: String,
+    unit: String,
+    /// Time of each value in seconds
+    times: Vec<f64>,
+    values: Vec<f64>,
+}
+
+impl Column {
+    /// Splits a `Name [unit]` header field
+    fn from_header(field: &str) -> Self {
+        let (name, unit) = field
+            .strip_suffix(']')
+            .and_then(|f| f.rsplit_once(" ["))
+            .unwrap_or((field, ""));
+        Self {
+            name: name.to_string(),
+            unit: unit.to_string(),
+            times: Vec::new(),
+            values: Vec::new(),
+        }
+    }
+}
+
+/// Sample rate in Hz of values logged at `times`, `None` if there are less than 2 of them
+fn infer_rate(times: &[f64]) -> Option<u16> {
+    let (first, last) = (times.first()?, times.last()?);
+    if times.len() < 2 {
+        return None;
+    }
+    let rate = (times.len() - 1) as f64 / (last - first);
+    Some(rate.round().clamp(1.0, u16::MAX as f64) as u16)
+}
+
+/// Stores integers that fit as [Datatype::I16], and everything else as [Datatype::F32]
+fn pick_datatype(values: &[f64]) -> (Datatype, i16) {
+    let fits_i16 = values
+        .iter()
+        .all(|v| v.fract() == 0.0 && *v >= i16::MIN as f64 && *v <= i16::MAX as f64);
+    if fits_i16 {
+        (Datatype::I16, 0)
+    } else {
+        (Datatype::F32, 0)
+    }
+}
+
+/// Parses a field as a number, blank fields have no value
+fn parse_value(field: &str, line: usize) -> I2Result<Option<f64>> {
+    let field = field.trim();
+    if field.is_empty() {
+        return Ok(None);
+    }
+    field
+        .parse()
+        .map(Some)
+        .map_err(|_| invalid_csv(line, &format!("{:?} is not a number", field)))
+}
+
+/// Splits a CSV line into fields, unquoting the ones quoted as in [crate::export_csv]
+fn split_fields(line: &str, line_number: usize) -> I2Result<Vec<String>> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    if quoted {
+        return Err(invalid_csv(line_number, "unterminated quoted field"));
+    }
+    fields.push(field);
+    Ok(fields)
+}
+
+fn invalid_csv(line: usize, message: &str) -> I2Error {
+    I2Error::InvalidCsv {
+        line,
+        message: message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{export_csv, import_csv, Datatype, I2Error, LDReader, LDWriter};
+    use std::fs;
+    use std::io::Cursor;
+
+    fn sample1_header() -> crate::Header {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        LDReader::new(&mut Cursor::new(bytes))
+            .read_header()
+            .unwrap()
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut csv = Vec::new();
+        export_csv(&mut LDReader::new(&mut Cursor::new(bytes)), &mut csv).unwrap();
+
+        let mut cursor = Cursor::new(Vec::new());
+        let mut writer = LDWriter::new(&mut cursor, sample1_header());
+        import_csv(&csv[..], &mut writer).unwrap();
+
+        let mut reader = LDReader::new(&mut cursor);
+        let channels = reader.read_channels().unwrap();
+        assert_eq!(channels.len(), 78);
+        assert_eq!(channels[0].name, "Air Temp Inlet");
+        assert_eq!(channels[0].unit, "C");
+        // The exported CSV repeats every channel at the highest sample rate
+        assert_eq!(channels[0].sample_rate, 100);
+        assert_eq!(channels[0].datatype, Datatype::F32);
+
+        let mut round_trip = Vec::new();
+        export_csv(&mut reader, &mut round_trip).unwrap();
+        let header = |csv: &[u8]| {
+            String::from_utf8(csv.to_vec())
+                .unwrap()
+                .lines()
+                .next()
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(header(&round_trip), header(&csv));
+    }
+
+    #[test]
+    fn csv_sparse_columns() {
+        let csv = "Time [s],Speed [km/h],\"Lap, Number\"\n\
+                   0,10.5,1\n\
+                   0.5,20,\n\
+                   1,30,2\n\
+                   1.5,40,\n";
+
+        let mut cursor = Cursor::new(Vec::new());
+        let mut writer = LDWriter::new(&mut cursor, sample1_header());
+        import_csv(csv.as_bytes(), &mut writer).unwrap();
+
+        let mut reader = LDReader::new(&mut cursor);
+        let channels = reader.read_channels().unwrap();
+        assert_eq!(channels[0].name, "Speed");
+        assert_eq!(channels[0].unit, "km/h");
+        assert_eq!(channels[0].sample_rate, 2);
+        assert_eq!(channels[0].datatype, Datatype::F32);
+        assert_eq!(
+            reader.channel_data_decoded(&channels[0]).unwrap(),
+            [10.5, 20.0, 30.0, 40.0]
+        );
+
+        assert_eq!(channels[1].name, "Lap, Number");
+        assert_eq!(channels[1].unit, "");
+        assert_eq!(channels[1].sample_rate, 1);
+        assert_eq!(channels[1].datatype, Datatype::I16);
+        assert_eq!(
+            reader.channel_data_decoded(&channels[1]).unwrap(),
+            [1.0, 2.0]
+        );
+    }
+
+    #[test]
+    fn csv_ragged_row() {
+        let csv = "Time [s],Speed [km/h]\n0,10\n0.5\n";
+
+        let mut cursor = Cursor::new(Vec::new());
+        let mut writer = LDWriter::new(&mut cursor, sample1_header());
+        match import_csv(csv.as_bytes(), &mut writer) {
+            Err(I2Error::InvalidCsv { line, message }) => {
+                assert_eq!(line, 3);
+                assert_eq!(message, "expected 2 fields like the header, found 1");
+            }
+            other => panic!("expected an InvalidCsv error, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use crate::{ChannelMetadata, Datatype, Event, Header, I2Result, LDReader, Vehicle, Venue};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek};
+
+/// A whole ld file, with the metadata of [crate::LDFile] and the decoded data of every channel
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LdFile {
+    pub header: Header,
+    pub event: Option<Event>,
+    pub venue: Option<Venue>,
+    pub vehicle: Option<Vehicle>,
+    pub channels: Vec<LdChannel>,
+}
+
+/// A channel of a [LdFile] along with its decoded data
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LdChannel {
+    #[serde(flatten)]
+    pub metadata: ChannelMetadata,
+    /// Decoded values, see [crate::Sample::decode_f64]. Empty for [Datatype::Invalid] channels
+    pub data: Vec<f64>,
+}
+
+impl<S: Read + Seek> LDReader<'_, S> {
+    /// Reads the whole file, including the decoded data of every channel, into a [LdFile]
+    pub fn read_ld_file(&mut self) -> I2Result<LdFile> {
+        let file = self.read_file()?;
+        let channels = file
+            .channels
+            .into_iter()
+            .map(|metadata| {
+                let data = if metadata.datatype == Datatype::Invalid {
+                    Vec::new()
+                } else {
+                    self.channel_data_decoded(&metadata)?
+                };
+                Ok(LdChannel { metadata, data })
+            })
+            .collect::<I2Result<_>>()?;
+
+        Ok(LdFile {
+            header: file.header,
+            event: file.event,
+            venue: file.venue,
+            vehicle: file.vehicle,
+            channels,
+        })
+    }
+
+    /// Serializes the whole file as JSON, see [LDReader::read_ld_file]
+    ///
+    /// Every sample of every channel ends up in the output, so this is best kept for small logs.
+    pub fn to_json(&mut self) -> I2Result<String> {
+        Ok(serde_json::to_string(&self.read_ld_file()?)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{LDReader, LdFile};
+    use std::fs;
+    use std::io::Cursor;
+
+    #[test]
+    fn sample1_json() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+
+        let json = reader.to_json().unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["header"]["venue"], "Calder");
+        assert_eq!(value["venue"]["name"], "Calder");
+
+        let channels = value["channels"].as_array().unwrap();
+        assert_eq!(channels.len(), 78);
+        assert_eq!(channels[0]["name"], "Air Temp Inlet");
+        assert_eq!(channels[0]["data"].as_array().unwrap().len(), 908);
+
+        let file: LdFile = serde_json::from_str(&json).unwrap();
+        assert_eq!(file, reader.read_ld_file().unwrap());
+    }
+}
@@ -0,0 +1,159 @@
+use crate::{ChannelMetadata, Header, I2Error, I2Result, LDWriter, Sample};
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::Path;
+
+/// The contents of a `.ldx` file, the XML sidecar that MoTeC i2 keeps next to a `.ld` file
+///
+/// Only the beacon markers (which delimit laps) and the details strings are supported.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Ldx {
+    /// Times in seconds since the start of the log at which a beacon was crossed
+    ///
+    /// Each pair of consecutive beacons is a lap, the time before the first beacon is the out
+    /// lap and the time after the last one is the in lap.
+    pub beacons: Vec<f64>,
+    /// `(id, value)` pairs of the details section, such as `("Total Laps", "3")`
+    pub details: Vec<(String, String)>,
+}
+
+impl Ldx {
+    /// Writes the XML for this sidecar
+    pub fn write<W: Write>(&self, w: &mut W) -> I2Result<()> {
+        writeln!(w, "<?xml version=\"1.0\"?>")?;
+        writeln!(
+            w,
+            "<LDXFile Locale=\"English_United Kingdom.1252\" DefaultLocale=\"C\" Version=\"1.6\">"
+        )?;
+        writeln!(w, " <Layers>")?;
+        writeln!(w, "  <Layer>")?;
+        writeln!(w, "   <MarkerBlock>")?;
+        writeln!(
+            w,
+            "    <MarkerGroup Name=\"Beacons\" Index=\"{}\">",
+            self.beacons.len()
+        )?;
+        for (i, time) in self.beacons.iter().enumerate() {
+            // Marker times are stored in microseconds
+            writeln!(
+                w,
+                "     <Marker Version=\"100\" ClassName=\"BCN\" Name=\"Manual.{}\" Flags=\"77\" Time=\"{:e}\"/>",
+                i + 1,
+                time * 1e6
+            )?;
+        }
+        writeln!(w, "    </MarkerGroup>")?;
+        writeln!(w, "   </MarkerBlock>")?;
+        writeln!(w, "  </Layer>")?;
+        writeln!(w, "  <Details>")?;
+        for (id, value) in self.details.iter() {
+            writeln!(
+                w,
+                "   <String Id=\"{}\" Value=\"{}\"/>",
+                escape(id),
+                escape(value)
+            )?;
+        }
+        writeln!(w, "  </Details>")?;
+        writeln!(w, " </Layers>")?;
+        writeln!(w, "</LDXFile>")?;
+        Ok(())
+    }
+
+    /// Reads the beacons and details of a `.ldx` file
+    ///
+    /// This is not a general XML parser, it only looks for the `Marker` and `String` elements
+    /// in the layout that MoTeC i2 (and [Ldx::write]) produces.
+    pub fn read<R: Read>(r: &mut R) -> I2Result<Self> {
+        let mut xml = String::new();
+        r.read_to_string(&mut xml)?;
+
+        let mut ldx = Ldx::default();
+        for element in xml.split('<').skip(1) {
+            if let Some(attrs) = element.strip_prefix("Marker ") {
+                let time = attribute(attrs, "Time")?;
+                let time: f64 = time
+                    .parse()
+                    .map_err(|_| I2Error::InvalidLdx(format!("Invalid marker time: {}", time)))?;
+                ldx.beacons.push(time / 1e6);
+            } else if let Some(attrs) = element.strip_prefix("String ") {
+                ldx.details
+                    .push((attribute(attrs, "Id")?, attribute(attrs, "Value")?));
+            }
+        }
+        Ok(ldx)
+    }
+}
+
+/// Writes a `.ld` file at `path` along with its `.ldx` sidecar
+pub fn write_ld_with_ldx(
+    path: impl AsRef<Path>,
+    header: Header,
+    channels: Vec<(ChannelMetadata, Vec<Sample>)>,
+    ldx: &Ldx,
+) -> I2Result<()> {
+    let path = path.as_ref();
+
+    let mut ld = BufWriter::new(File::create(path)?);
+    channels
+        .into_iter()
+        .fold(
+            LDWriter::new(&mut ld, header),
+            |writer, (channel, samples)| writer.with_channel(channel, samples),
+        )
+        .write()?;
+    ld.flush()?;
+
+    let mut sidecar = BufWriter::new(File::create(path.with_extension("ldx"))?);
+    ldx.write(&mut sidecar)?;
+    sidecar.flush()?;
+    Ok(())
+}
+
+/// Finds the value of attribute `name` in the attributes of an element
+fn attribute(attrs: &str, name: &str) -> I2Result<String> {
+    let start = format!("{}=\"", name);
+    attrs
+        .split_once(&start)
+        .and_then(|(_, rest)| rest.split_once('"'))
+        .map(|(value, _)| unescape(value))
+        .ok_or_else(|| I2Error::InvalidLdx(format!("Missing attribute {}", name)))
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn unescape(s: &str) -> String {
+    s.replace("&quot;", "\"")
+        .replace("&gt;", ">")
+        .replace("&lt;", "<")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Ldx;
+    use std::io::Cursor;
+
+    #[test]
+    fn ldx_round_trip() {
+        let ldx = Ldx {
+            beacons: vec![60.5, 122.25],
+            details: vec![
+                ("Total Laps".to_string(), "2".to_string()),
+                ("Fastest Time".to_string(), "1:01.750".to_string()),
+                ("Comment".to_string(), "\"wet\" & <cold>".to_string()),
+            ],
+        };
+
+        let mut xml = Vec::new();
+        ldx.write(&mut xml).unwrap();
+
+        let read = Ldx::read(&mut Cursor::new(xml)).unwrap();
+        assert_eq!(read, ldx);
+    }
+}
@@ -1,10 +1,67 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+mod datatype;
 mod error;
+mod view;
+
+#[cfg(feature = "std")]
+mod analysis;
+#[cfg(feature = "std")]
+mod export;
+#[cfg(feature = "std")]
 mod full_header;
+#[cfg(feature = "std")]
+mod import;
+#[cfg(feature = "std")]
+mod ldx;
+#[cfg(feature = "std")]
 mod reader;
+#[cfg(feature = "std")]
 mod structs;
+#[cfg(feature = "std")]
 mod writer;
 
+#[cfg(feature = "tokio")]
+mod async_reader;
+#[cfg(feature = "polars")]
+mod dataframe;
+#[cfg(feature = "serde")]
+mod json;
+#[cfg(feature = "ndarray")]
+mod matrix;
+#[cfg(feature = "mmap")]
+mod mmap;
+#[cfg(feature = "rayon")]
+mod parallel;
+#[cfg(feature = "hound")]
+mod wav;
+
+pub use datatype::*;
 pub use error::*;
+pub use view::*;
+
+#[cfg(feature = "std")]
+pub use analysis::*;
+#[cfg(feature = "std")]
+pub use export::*;
+#[cfg(feature = "std")]
+pub use import::*;
+#[cfg(feature = "std")]
+pub use ldx::*;
+#[cfg(feature = "std")]
 pub use reader::*;
+#[cfg(feature = "std")]
 pub use structs::*;
+#[cfg(feature = "std")]
 pub use writer::*;
+
+#[cfg(feature = "tokio")]
+pub use async_reader::*;
+#[cfg(feature = "serde")]
+pub use json::*;
+#[cfg(feature = "mmap")]
+pub use mmap::*;
+#[cfg(feature = "hound")]
+pub use wav::*;
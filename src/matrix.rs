@@ -0,0 +1,76 @@
+use crate::export::Resampled;
+use crate::{I2Result, LDReader};
+use ndarray::Array2;
+use std::io::{Read, Seek};
+
+impl<S: Read + Seek> LDReader<'_, S> {
+    /// Reads every channel into a dense matrix of decoded values, with one row per time step
+    /// and one column per channel
+    ///
+    /// There is one row per sample of the channel with the highest sample rate, and channels
+    /// with a lower sample rate hold each value until their next sample. Channels that end
+    /// before the others hold their last value, and channels without any samples are NaN.
+    /// Channels with a [crate::Datatype::Invalid] or a 0 sample rate are skipped.
+    ///
+    /// Returns the names of the channels in column order alongside the matrix.
+    pub fn to_ndarray(&mut self) -> I2Result<(Vec<String>, Array2<f64>)> {
+        let resampled = Resampled::read(self)?;
+
+        let names = resampled.channels.iter().map(|c| c.name.clone()).collect();
+        let shape = (resampled.rows as usize, resampled.channels.len());
+        let matrix = Array2::from_shape_fn(shape, |(row, column)| {
+            resampled
+                .value(column, row as u64)
+                .or_else(|| resampled.channels[column].values.last().copied())
+                .unwrap_or(f64::NAN)
+        });
+
+        Ok((names, matrix))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{LDReader, LDWriter};
+    use std::fs;
+    use std::io::Cursor;
+
+    #[test]
+    fn sample1_ndarray() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+
+        let (names, matrix) = reader.to_ndarray().unwrap();
+        // 454 seconds at 100Hz, the rate of the Susp Pos channels
+        assert_eq!(matrix.dim(), (45400, 78));
+        assert_eq!(names.len(), 78);
+        assert_eq!(names[0], "Air Temp Inlet");
+
+        // Air Temp Inlet is logged at 2Hz, so each value is repeated for 50 rows
+        assert!((matrix[[0, 0]] - 19.9).abs() < 0.000001);
+        assert!((matrix[[49, 0]] - 19.9).abs() < 0.000001);
+        assert!((matrix[[100, 0]] - 20.1).abs() < 0.000001);
+    }
+
+    #[test]
+    fn ndarray_holds_short_channels() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let header = LDReader::new(&mut Cursor::new(bytes))
+            .read_header()
+            .unwrap();
+
+        let mut cursor = Cursor::new(Vec::new());
+        let mut writer = LDWriter::new(&mut cursor, header);
+        writer.log("Lap", "", 1, &[1.0]).unwrap();
+        writer.log("Speed", "km/h", 2, &[10.0, 20.0, 30.0]).unwrap();
+
+        let mut reader = LDReader::new(&mut cursor);
+        let (names, matrix) = reader.to_ndarray().unwrap();
+        assert_eq!(names, ["Lap", "Speed"]);
+        assert_eq!(
+            matrix,
+            ndarray::array![[1.0, 10.0], [1.0, 20.0], [1.0, 30.0]]
+        );
+    }
+}
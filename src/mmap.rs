@@ -0,0 +1,107 @@
+use crate::{I2Result, LDReader};
+use memmap2::Mmap;
+use std::fs::File;
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// A ld file mapped into memory, see [LDReader::from_mmap]
+#[derive(Debug)]
+pub struct MappedFile {
+    cursor: Cursor<Mmap>,
+}
+
+impl MappedFile {
+    /// Maps the file at `path` into memory
+    ///
+    /// The file must not be modified while it is mapped, as that is visible through the map.
+    pub fn open(path: impl AsRef<Path>) -> I2Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: We never write to the map, but it is unsound if the file is modified by
+        // someone else while mapped. This is the same caveat as with every other mmap.
+        let map = unsafe { Mmap::map(&file)? };
+        Ok(Self {
+            cursor: Cursor::new(map),
+        })
+    }
+
+    /// The contents of the whole file
+    pub fn bytes(&self) -> &[u8] {
+        self.cursor.get_ref()
+    }
+}
+
+impl Read for MappedFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.cursor.read(buf)
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        self.cursor.read_exact(buf)
+    }
+}
+
+impl Seek for MappedFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.cursor.seek(pos)
+    }
+}
+
+impl<'a> LDReader<'a, MappedFile> {
+    /// Creates a reader over a memory mapped file
+    ///
+    /// Reads go straight to the mapped memory without any syscalls, and
+    /// [LDReader::channel_data] decodes samples directly from the mapped bytes.
+    pub fn from_mmap(file: &'a mut MappedFile) -> Self {
+        Self::new(file).with_mapped(MappedFile::bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{LDReader, MappedFile};
+    use std::fs;
+    use std::io::Cursor;
+
+    #[test]
+    fn mmap_matches_cursor() {
+        let mut cursor = Cursor::new(fs::read("./samples/Sample1.ld").unwrap());
+        let mut cursor_reader = LDReader::new(&mut cursor);
+        let mut file = MappedFile::open("./samples/Sample1.ld").unwrap();
+        let mut mmap_reader = LDReader::from_mmap(&mut file);
+
+        assert_eq!(
+            mmap_reader.read_header().unwrap(),
+            cursor_reader.read_header().unwrap()
+        );
+        let channels = cursor_reader.read_channels().unwrap();
+        assert_eq!(mmap_reader.read_channels().unwrap(), channels);
+
+        let cursor_data: Vec<_> = channels
+            .iter()
+            .map(|c| cursor_reader.channel_data(c).unwrap())
+            .collect();
+        let mmap_data: Vec<_> = channels
+            .iter()
+            .map(|c| mmap_reader.channel_data(c).unwrap())
+            .collect();
+        assert_eq!(mmap_data, cursor_data);
+    }
+
+    #[test]
+    fn mmap_truncated_channel() {
+        let mut file = MappedFile::open("./samples/Sample1.ld").unwrap();
+        let mut reader = LDReader::from_mmap(&mut file);
+        let mut channel = reader.read_channels().unwrap().remove(0);
+        channel.data_addr = file_len() - 4;
+
+        assert!(reader.channel_data(&channel).is_err());
+
+        let mut reader = reader.with_partial_reads();
+        assert_eq!(reader.channel_data(&channel).unwrap().len(), 2);
+        assert_eq!(reader.partially_read().unwrap().read, 2);
+    }
+
+    fn file_len() -> u32 {
+        fs::metadata("./samples/Sample1.ld").unwrap().len() as u32
+    }
+}
@@ -0,0 +1,104 @@
+use crate::reader::{channel_bytes, decode_samples};
+use crate::{ChannelMetadata, Datatype, I2Error, I2Result, LDReader, LDView, Sample};
+use rayon::prelude::*;
+use std::io::Cursor;
+
+impl<'a, 'b> LDReader<'a, Cursor<&'b [u8]>> {
+    /// Reads the samples of all `channels` in parallel, from `bytes` holding the whole file
+    ///
+    /// A [LDReader] needs exclusive access to its source, so this decodes straight from the
+    /// bytes instead, with one task per channel. The result is the same as calling
+    /// [LDReader::channel_data] for each channel, in the same order as `channels`.
+    pub fn read_all_channel_data_par(
+        bytes: &[u8],
+        channels: &[ChannelMetadata],
+    ) -> I2Result<Vec<(ChannelMetadata, Vec<Sample>)>> {
+        // A 0 data pointer in the header means that no channel has data
+        let header_has_data = LDView::new(bytes)?.channel_data_ptr()? != 0;
+
+        channels
+            .par_iter()
+            .map(|channel| {
+                let mut samples = Vec::new();
+                if !header_has_data || channel.data_addr == 0 {
+                    return Ok((channel.clone(), samples));
+                }
+
+                if channel.datatype == Datatype::Invalid && channel.data_count != 0 {
+                    return Err(I2Error::InvalidChannelData {
+                        name: channel.name.clone(),
+                    });
+                }
+
+                let data = channel_bytes(bytes, channel, false)?;
+                decode_samples(data, channel, &mut samples);
+                Ok((channel.clone(), samples))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ChannelMetadata, Datatype, LDReader, LDWriter, Sample};
+    use std::fs;
+    use std::io::Cursor;
+
+    #[test]
+    fn sample1_par_matches_sequential() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut cursor = Cursor::new(bytes.as_slice());
+        let mut reader = LDReader::new(&mut cursor);
+        let channels = reader.read_channels().unwrap();
+
+        let sequential: Vec<_> = channels
+            .iter()
+            .map(|c| (c.clone(), reader.channel_data(c).unwrap()))
+            .collect();
+        let parallel = LDReader::read_all_channel_data_par(&bytes, &channels).unwrap();
+        assert_eq!(parallel, sequential);
+    }
+
+    #[test]
+    fn large_file_par_matches_sequential() {
+        let header = LDReader::new(&mut Cursor::new(fs::read("./samples/Sample1.ld").unwrap()))
+            .read_header()
+            .unwrap();
+
+        let mut cursor = Cursor::new(Vec::new());
+        let mut writer = LDWriter::new(&mut cursor, header);
+        for i in 0..16 {
+            let channel = ChannelMetadata {
+                prev_addr: 0,
+                next_addr: 0,
+                data_addr: 0,
+                data_count: 0,
+                datatype: Datatype::I32,
+                sample_rate: 1000,
+                offset: 0,
+                mul: 1,
+                scale: 1,
+                dec_places: 0,
+                name: format!("Channel {}", i),
+                short_name: format!("Ch{}", i),
+                unit: "".to_string(),
+            };
+            let samples: Vec<Sample> = (0..200_000).map(|s| Sample::I32(s * i)).collect();
+            let mut handle = writer.begin_channel(channel).unwrap();
+            handle.write_block(&samples).unwrap();
+            handle.finish().unwrap();
+        }
+        let bytes = cursor.into_inner();
+
+        let mut cursor = Cursor::new(bytes.as_slice());
+        let mut reader = LDReader::new(&mut cursor);
+        let channels = reader.read_channels().unwrap();
+
+        let sequential: Vec<_> = channels
+            .iter()
+            .map(|c| (c.clone(), reader.channel_data(c).unwrap()))
+            .collect();
+        let parallel = LDReader::read_all_channel_data_par(&bytes, &channels).unwrap();
+        assert_eq!(parallel, sequential);
+    }
+}
@@ -1,14 +1,55 @@
-use crate::{ChannelMetadata, Datatype, Event, Header, I2Error, I2Result, Sample, Vehicle, Venue};
+use crate::full_header::FULL_HEADER;
+use crate::structs::f16_to_f32;
+use crate::{
+    convert_unit, Beacon, ChannelMetadata, ChecksumStatus, Datatype, DecodedChannel, DriverInputs,
+    Event, FileAddr, FileReport, FormatVariant, Header, I2Error, I2Result, LDFile, Lap, LapKind,
+    PartiallyRead, RangeWarning, Sample, SampleCountMismatch, TimeBase, Vehicle, Venue, Warning,
+    LD_HEADER_MARKER,
+};
 use byteorder::{LittleEndian, ReadBytesExt};
-use std::io::{Read, Seek, SeekFrom};
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::ops::RangeInclusive;
+use std::path::Path;
 use std::{io, iter};
 
-pub(crate) const LD_HEADER_MARKER: u32 = 64;
+/// Marks the start of the extension block, see [crate::LDWriter::set_extension]
+pub(crate) const EXTENSION_MARKER: [u8; 8] = *b"I2EXTBLK";
+
+/// Opens the ld file at `path` and reads all of its metadata
+///
+/// Sample data is not read, use [LDReader] for that.
+pub fn read_file(path: impl AsRef<Path>) -> I2Result<LDFile> {
+    let mut file = BufReader::new(File::open(path)?);
+    LDReader::new(&mut file).read_file()
+}
 
 #[derive(Debug)]
 pub struct LDReader<'a, S: Read + Seek> {
     source: &'a mut S,
     header: Option<Header>,
+    /// Classification of the file, set when reading the header
+    format_variant: Option<FormatVariant>,
+    cache: Option<SampleCache>,
+    decode_tables: DecodeTables,
+    /// Buffer reused across reads of channel data
+    scratch: Vec<u8>,
+    /// Channels with a [Datatype::Invalid] found by the last channel list walk
+    invalid_channels: Vec<ChannelMetadata>,
+    /// Bounds for decoded sample values, see [LDReader::with_range_check]
+    range_check: Option<RangeInclusive<f64>>,
+    /// Samples outside of `range_check` found by the last channel data read
+    range_warnings: Vec<RangeWarning>,
+    /// See [LDReader::with_partial_reads]
+    partial_reads: bool,
+    /// Set if the last channel data read was truncated
+    partially_read: Option<PartiallyRead>,
+    /// See [LDReader::with_strict]
+    strict: bool,
+    /// Returns the whole file, if the source is held in memory. Channel data is then decoded
+    /// straight from it instead of being read into `scratch` first.
+    mapped: Option<fn(&S) -> &[u8]>,
 }
 
 impl<'a, S: Read + Seek> LDReader<'a, S> {
@@ -16,11 +57,90 @@ impl<'a, S: Read + Seek> LDReader<'a, S> {
         Self {
             source,
             header: None,
+            format_variant: None,
+            cache: None,
+            decode_tables: DecodeTables::default(),
+            scratch: Vec::new(),
+            invalid_channels: Vec::new(),
+            range_check: None,
+            range_warnings: Vec::new(),
+            partial_reads: false,
+            partially_read: None,
+            strict: false,
+            mapped: None,
         }
     }
 
-    // TODO: Remove asserts and change into a proper error type
+    /// Caches the samples of up to `capacity` channels returned by [LDReader::channel_data]
+    ///
+    /// The least recently used channel is evicted when the cache is full. Channels are keyed
+    /// by their `data_addr`, `data_count` and datatype, so this assumes that the underlying
+    /// source is not modified while the reader is in use.
+    pub fn with_cache(mut self, capacity: usize) -> Self {
+        self.cache = Some(SampleCache::new(capacity));
+        self
+    }
+
+    /// Checks that the decoded value of every sample read by [LDReader::channel_data] is
+    /// within `bounds`
+    ///
+    /// Samples outside of it don't cause an error, they are reported by
+    /// [LDReader::range_warnings]. This is useful to detect corrupted data, such as bit flips.
+    pub fn with_range_check(mut self, bounds: RangeInclusive<f64>) -> Self {
+        self.range_check = Some(bounds);
+        self
+    }
+
+    /// Returns the samples that are available when a channel's data section is cut short by the
+    /// end of the file, instead of failing
+    ///
+    /// This allows salvaging data from truncated logs. Check [LDReader::partially_read] to find
+    /// out if the last read of channel data was incomplete.
+    pub fn with_partial_reads(mut self) -> Self {
+        self.partial_reads = true;
+        self
+    }
+
+    /// Decodes channel data directly from the bytes returned by `mapped`, which must be the
+    /// whole contents of the source
+    #[cfg_attr(not(feature = "mmap"), allow(dead_code))]
+    pub(crate) fn with_mapped(mut self, mapped: fn(&S) -> &[u8]) -> Self {
+        self.mapped = Some(mapped);
+        self
+    }
+
+    /// Checks that the channel list has as many channels as [Header::num_channels] says
+    ///
+    /// When enabled, reading the channel list fails with [I2Error::ChannelCountMismatch] if they
+    /// disagree. This is disabled by default, and the channel list is trusted.
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Set if the last read of channel data returned less samples than the channel has
+    ///
+    /// This only happens with [LDReader::with_partial_reads].
+    pub fn partially_read(&self) -> Option<&PartiallyRead> {
+        self.partially_read.as_ref()
+    }
+
+    /// Samples outside of the range check bounds found by the last read of channel data
+    ///
+    /// This is always empty unless the reader was built with [LDReader::with_range_check].
+    pub fn range_warnings(&self) -> &[RangeWarning] {
+        &self.range_warnings
+    }
+
+    /// Reads the header at the start of the file
+    ///
+    /// Fails with [I2Error::UnexpectedEof] if the file ends before the header does.
     pub fn read_header(&mut self) -> I2Result<Header> {
+        self.parse_header().map_err(eof_context("header", 0))
+    }
+
+    // TODO: Remove asserts and change into a proper error type
+    fn parse_header(&mut self) -> I2Result<Header> {
         // Header is always at start
         self.source.seek(SeekFrom::Start(0))?;
 
@@ -48,35 +168,51 @@ impl<'a, S: Read + Seek> LDReader<'a, S> {
         // assert_eq!(_unknown, [0u8; 24]);
 
         // TODO: These may not actually be const...
+        // They are different in files produced by converters, see [FormatVariant]
         let _unknown_const_1 = self.source.read_u16::<LittleEndian>()?;
         // assert_eq!(_unknown_const_1, 0x0000);
-        let _unknown_const_2 = self.source.read_u16::<LittleEndian>()?;
+        let unknown_const_2 = self.source.read_u16::<LittleEndian>()?;
         // assert_eq!(_unknown_const_2, 0x4240);
-        let _unknown_const_3 = self.source.read_u16::<LittleEndian>()?;
+        let unknown_const_3 = self.source.read_u16::<LittleEndian>()?;
         // assert_eq!(_unknown_const_3, 0x000F);
 
         let device_serial = self.source.read_u32::<LittleEndian>()?;
         let device_type = self.read_string(8)?;
         let device_version = self.source.read_u16::<LittleEndian>()?;
 
-        // TODO: This may not actually be const...
-        let _unknown_const_4 = self.source.read_u16::<LittleEndian>()?;
-        // assert_eq!(_unknown_const_4, 0x0080);
+        // 0x0080 in Sample1.ld, see [Header::device_flags]
+        let device_flags = self.source.read_u16::<LittleEndian>()?;
 
         let num_channels = self.source.read_u32::<LittleEndian>()?;
-        let _unknown = self.source.read_u32::<LittleEndian>()?;
+        let unknown_const_5 = self.source.read_u32::<LittleEndian>()?;
+
+        let consts = (
+            unknown_const_2,
+            unknown_const_3,
+            device_flags,
+            unknown_const_5,
+        );
+        self.format_variant = Some(if consts == (0x4240, 0x000F, 0x0080, 0x0001_0064) {
+            FormatVariant::Standard
+        } else {
+            FormatVariant::Converted
+        });
 
         let date_string = self.read_string(16)?;
         let _unknown = self.read_bytes(16)?;
         let time_string = self.read_string(16)?;
         let _unknown = self.read_bytes(16)?;
 
+        // We've looked for additional driver info (number, team, class) around the driver
+        // field, but the unknown regions below are all zeros in Sample1.ld, so there is
+        // nothing to expose for now.
         let driver = self.read_string(64)?;
         let vehicleid = self.read_string(64)?;
         let _unknown = self.read_bytes(64)?;
         let venue = self.read_string(64)?;
         let _unknown = self.read_bytes(64)?;
 
+        // All zeros in Sample1.ld
         let _unknown = self.read_bytes(1024)?;
 
         let _pro_logging_bytes = self.source.read_u32::<LittleEndian>()?;
@@ -95,6 +231,7 @@ impl<'a, S: Read + Seek> LDReader<'a, S> {
             device_serial,
             device_type,
             device_version,
+            device_flags,
             num_channels,
             date_string,
             time_string,
@@ -108,23 +245,40 @@ impl<'a, S: Read + Seek> LDReader<'a, S> {
         Ok(header)
     }
 
+    /// Classifies the file based on the header fields that are constant in files logged by
+    /// MoTeC devices
+    ///
+    /// These are the three u16 at offset 64, the u16 at offset 84 and the u32 at offset 90.
+    /// Files converted from other formats don't always use the same values, but are otherwise
+    /// read the same way.
+    pub fn format_variant(&mut self) -> I2Result<FormatVariant> {
+        if self.format_variant.is_none() {
+            self.read_header()?;
+        }
+        Ok(self.format_variant.unwrap())
+    }
+
     pub fn read_event(&mut self) -> I2Result<Option<Event>> {
         if self.header.is_none() {
             self.read_header()?;
         }
 
-        let event_ptr = self.header.as_ref().unwrap().event_ptr;
-        if event_ptr == 0 {
+        let event_ptr = FileAddr::from(self.header.as_ref().unwrap().event_ptr);
+        if event_ptr.is_zero() {
             return Ok(None);
         }
 
-        self.source.seek(SeekFrom::Start(event_ptr as u64))?;
+        event_ptr.seek(self.source)?;
 
         let name = self.read_string(64)?;
         let session = self.read_string(64)?;
         let comment = self.read_string(1024)?;
         let venue_addr = self.source.read_u16::<LittleEndian>()?;
 
+        // We've looked for a lap count or best lap summary after this. In Sample1.ld there is
+        // a 0 u16 followed by what looks like a u32 pointer (0x2C48), but that points into an
+        // all zeros region of the header, so there is nothing to expose for now.
+
         Ok(Some(Event {
             name,
             session,
@@ -136,17 +290,22 @@ impl<'a, S: Read + Seek> LDReader<'a, S> {
     pub fn read_venue(&mut self) -> I2Result<Option<Venue>> {
         Ok(match self.read_event()? {
             Some(event) => {
-                if event.venue_addr == 0 {
+                let venue_addr = FileAddr::from(event.venue_addr);
+                if venue_addr.is_zero() {
                     return Ok(None);
                 }
 
-                self.source.seek(SeekFrom::Start(event.venue_addr as u64))?;
+                venue_addr.seek(self.source)?;
 
                 let name = self.read_string(64)?;
-                let _unknown = self.read_bytes(1034)?;
+                let raw_extra = self.read_bytes(Venue::RAW_EXTRA_SIZE)?;
                 let vehicle_addr = self.source.read_u16::<LittleEndian>()?;
 
-                Some(Venue { name, vehicle_addr })
+                Some(Venue {
+                    name,
+                    raw_extra,
+                    vehicle_addr,
+                })
             }
             None => None,
         })
@@ -155,12 +314,12 @@ impl<'a, S: Read + Seek> LDReader<'a, S> {
     pub fn read_vehicle(&mut self) -> I2Result<Option<Vehicle>> {
         Ok(match self.read_venue()? {
             Some(venue) => {
-                if venue.vehicle_addr == 0 {
+                let vehicle_addr = FileAddr::from(venue.vehicle_addr);
+                if vehicle_addr.is_zero() {
                     return Ok(None);
                 }
 
-                self.source
-                    .seek(SeekFrom::Start(venue.vehicle_addr as u64))?;
+                vehicle_addr.seek(self.source)?;
 
                 let id = self.read_string(64)?;
                 let _unknown = self.read_bytes(128)?;
@@ -179,35 +338,342 @@ impl<'a, S: Read + Seek> LDReader<'a, S> {
         })
     }
 
+    /// Reads the header, event, venue, vehicle and channel metadata of the file
+    pub fn read_file(&mut self) -> I2Result<LDFile> {
+        Ok(LDFile {
+            header: self.read_header()?,
+            event: self.read_event()?,
+            venue: self.read_venue()?,
+            vehicle: self.read_vehicle()?,
+            channels: self.read_channels()?,
+        })
+    }
+
     /// Read the channel meta data blocks inside the ld file
     ///
     /// The channel metadata structs form a linked list with each metadata block pointing
-    /// to the next block
+    /// to the next block. Fails with [I2Error::ChannelListCycle] if the list loops back on
+    /// itself.
     ///
     /// Calls [LDReader::read_header] if it hasn't been called before
     pub fn read_channels(&mut self) -> I2Result<Vec<ChannelMetadata>> {
+        self.read_channels_filtered(|_| true)
+    }
+
+    /// Read the channel meta data blocks for which `pred` returns true
+    ///
+    /// This walks the whole linked list, but channel data is never read.
+    ///
+    /// See [LDReader::read_channels]
+    pub fn read_channels_filtered(
+        &mut self,
+        pred: impl Fn(&ChannelMetadata) -> bool,
+    ) -> I2Result<Vec<ChannelMetadata>> {
         if self.header.is_none() {
             self.read_header()?;
         }
 
         let mut channels = vec![];
+        // Corrupted files may have a list that loops back on itself
+        let mut visited = HashSet::new();
+        self.invalid_channels.clear();
 
-        let mut next_ptr = self.header.as_ref().unwrap().channel_meta_ptr;
+        let mut trailer_size = ChannelMetadata::TRAILER_SIZE;
+        let mut next_ptr = FileAddr::from(self.header.as_ref().unwrap().channel_meta_ptr);
         loop {
             // A 0 addr means we are done searching this list
-            if next_ptr == 0 {
+            if next_ptr.is_zero() {
+                let header = self.header.as_ref().unwrap().num_channels;
+                let found = visited.len() as u32;
+                if self.strict && found != header {
+                    return Err(I2Error::ChannelCountMismatch { header, found });
+                }
                 return Ok(channels);
             }
+            if !visited.insert(next_ptr) {
+                return Err(I2Error::ChannelListCycle { at: next_ptr.0 });
+            }
+
+            let (channel, trailer) = self.read_channel_metadata(next_ptr, trailer_size)?;
+            trailer_size = trailer;
+            next_ptr = FileAddr::from(channel.next_addr);
+            if channel.datatype == Datatype::Invalid {
+                self.invalid_channels.push(channel.clone());
+            }
+            if pred(&channel) {
+                channels.push(channel);
+            }
+        }
+    }
+
+    /// Counts the channels in the file by following the channel metadata linked list
+    ///
+    /// Only the `next_addr` of each block is read, which makes this cheaper than
+    /// [LDReader::read_channels]. Unlike [Header::num_channels] this is always the number of
+    /// channels that can actually be read.
+    pub fn channel_count(&mut self) -> I2Result<usize> {
+        if self.header.is_none() {
+            self.read_header()?;
+        }
+
+        let mut visited = HashSet::new();
+        let mut next_ptr = FileAddr::from(self.header.as_ref().unwrap().channel_meta_ptr);
+        while !next_ptr.is_zero() {
+            if !visited.insert(next_ptr) {
+                return Err(I2Error::ChannelListCycle { at: next_ptr.0 });
+            }
+
+            next_ptr.seek(self.source)?;
+            let _prev_addr = self.source.read_u32::<LittleEndian>()?;
+            next_ptr = FileAddr::from(self.source.read_u32::<LittleEndian>()?);
+        }
+
+        Ok(visited.len())
+    }
+
+    /// Checks that the structure of the file is sound, without reading any channel data
+    ///
+    /// This checks the header marker, that the header pointers and every channel's metadata and
+    /// data fall inside the file, and that the channel list ends. Channel entries with a
+    /// datatype we don't know or strings that aren't utf8 are reported and skipped. Problems are
+    /// collected into a list of [Warning]s instead of stopping at the first one, so an empty list
+    /// means that the file looks fine. Errors are only returned if the source itself fails.
+    pub fn validate(&mut self) -> I2Result<Vec<Warning>> {
+        let file_size = self.source.seek(SeekFrom::End(0))?;
+
+        let header = match self.read_header() {
+            Ok(header) => header,
+            Err(I2Error::InvalidHeaderMarker { found, .. }) => {
+                return Ok(vec![Warning::InvalidHeaderMarker { found }])
+            }
+            Err(I2Error::UnexpectedEof { .. }) => return Ok(vec![Warning::TruncatedHeader]),
+            Err(e) => return Err(e),
+        };
+
+        let mut warnings = Vec::new();
+        // The data section is empty in files without samples, and may start at the end
+        let pointers = [
+            ("channel_meta_ptr", header.channel_meta_ptr, file_size),
+            ("channel_data_ptr", header.channel_data_ptr, file_size + 1),
+            ("event_ptr", header.event_ptr, file_size),
+        ];
+        for (pointer, addr, end) in pointers {
+            if addr as u64 >= end {
+                warnings.push(Warning::PointerOutOfBounds {
+                    pointer,
+                    addr,
+                    file_size,
+                });
+            }
+        }
+
+        let mut visited = HashSet::new();
+        let mut trailer_size = ChannelMetadata::TRAILER_SIZE;
+        let mut next_ptr = FileAddr::from(header.channel_meta_ptr);
+        while !next_ptr.is_zero() {
+            if !visited.insert(next_ptr) {
+                warnings.push(Warning::ChannelListCycle { at: next_ptr.0 });
+                break;
+            }
+            if u64::from(next_ptr) + ChannelMetadata::ENTRY_SIZE as u64 > file_size {
+                warnings.push(Warning::MetadataOutOfBounds {
+                    addr: next_ptr.0,
+                    file_size,
+                });
+                break;
+            }
+
+            let (channel, trailer) = match self.read_channel_metadata(next_ptr, trailer_size) {
+                Ok(entry) => entry,
+                Err(e) => {
+                    warnings.push(match e {
+                        I2Error::UnrecognizedDatatype { _type, size } => {
+                            Warning::UnrecognizedDatatype {
+                                addr: next_ptr.0,
+                                _type,
+                                size,
+                            }
+                        }
+                        I2Error::NonUtf8String(_) => Warning::NonUtf8String { addr: next_ptr.0 },
+                        e => return Err(e),
+                    });
+                    // The rest of the entry can't be trusted, but the list may still go on
+                    FileAddr::from(next_ptr.0 + 4).seek(self.source)?;
+                    next_ptr = FileAddr::from(self.source.read_u32::<LittleEndian>()?);
+                    continue;
+                }
+            };
+            trailer_size = trailer;
+            next_ptr = FileAddr::from(channel.next_addr);
+
+            let data_end = channel.data_addr as u64 + channel.data_size() as u64;
+            if self.has_data(&channel) && data_end > file_size {
+                warnings.push(Warning::ChannelDataOutOfBounds {
+                    data_addr: channel.data_addr,
+                    data_size: channel.data_size(),
+                    channel: channel.name,
+                    file_size,
+                });
+            }
+        }
+
+        Ok(warnings)
+    }
+
+    /// Reads the metadata of the channel at `index` in the channel list
+    ///
+    /// Only the `next_addr` of the channels before it are read. Returns `None` if the list has
+    /// `index` or less channels.
+    pub fn channel(&mut self, index: usize) -> I2Result<Option<ChannelMetadata>> {
+        if self.header.is_none() {
+            self.read_header()?;
+        }
+
+        let mut addr = FileAddr::from(self.header.as_ref().unwrap().channel_meta_ptr);
+        for _ in 0..index {
+            if addr.is_zero() {
+                return Ok(None);
+            }
+
+            addr.seek(self.source)?;
+            let _prev_addr = self.source.read_u32::<LittleEndian>()?;
+            addr = FileAddr::from(self.source.read_u32::<LittleEndian>()?);
+        }
+
+        if addr.is_zero() {
+            return Ok(None);
+        }
+        let (channel, _) = self.read_channel_metadata(addr, ChannelMetadata::TRAILER_SIZE)?;
+        Ok(Some(channel))
+    }
+
+    /// Finds the first channel whose name is exactly `name`
+    pub fn channel_by_name(&mut self, name: &str) -> I2Result<Option<ChannelMetadata>> {
+        Ok(self
+            .read_channels_filtered(|c| c.name == name)?
+            .into_iter()
+            .next())
+    }
+
+    /// Finds the first channel whose name matches `name`, ignoring case
+    pub fn channel_by_name_ci(&mut self, name: &str) -> I2Result<Option<ChannelMetadata>> {
+        let name = name.to_lowercase();
+        Ok(self
+            .read_channels_filtered(|c| c.name.to_lowercase() == name)?
+            .into_iter()
+            .next())
+    }
+
+    /// Channels without any samples
+    ///
+    /// The iRacing mu exporter is known to produce these. Only the channel metadata is read.
+    pub fn empty_channels(&mut self) -> I2Result<Vec<ChannelMetadata>> {
+        self.read_channels_filtered(|c| c.data_count == 0)
+    }
+
+    /// Groups the channels by their sample rate
+    ///
+    /// Channels in the same group can be processed on a shared time base without resampling.
+    pub fn channels_grouped_by_rate(&mut self) -> I2Result<BTreeMap<u16, Vec<ChannelMetadata>>> {
+        let mut groups: BTreeMap<u16, Vec<ChannelMetadata>> = BTreeMap::new();
+        for channel in self.read_channels()? {
+            groups.entry(channel.sample_rate).or_default().push(channel);
+        }
+        Ok(groups)
+    }
+
+    /// Summarizes the whole file, this is meant for diagnostics
+    ///
+    /// Only the header and channel metadata are read.
+    pub fn report(&mut self) -> I2Result<FileReport> {
+        let header = self.read_header()?;
+        let channels = self.read_channels()?;
+
+        let mut datatypes: Vec<(Datatype, usize)> = Vec::new();
+        for channel in channels.iter() {
+            match datatypes.iter_mut().find(|(d, _)| *d == channel.datatype) {
+                Some((_, count)) => *count += 1,
+                None => datatypes.push((channel.datatype.clone(), 1)),
+            }
+        }
 
-            let channel = self.read_channel_metadata(next_ptr)?;
-            next_ptr = channel.next_addr;
-            channels.push(channel);
+        let mut rates = BTreeMap::new();
+        for channel in channels.iter() {
+            *rates.entry(channel.sample_rate).or_default() += 1;
         }
+
+        let duration_secs = channels
+            .iter()
+            .map(ChannelMetadata::duration_secs)
+            .fold(0.0, f64::max);
+
+        Ok(FileReport {
+            header,
+            channel_count: channels.len(),
+            datatypes,
+            rates,
+            total_samples: channels.iter().map(|c| c.data_count as u64).sum(),
+            duration_secs,
+            invalid_channels: self
+                .invalid_channels
+                .iter()
+                .map(|c| c.name.clone())
+                .collect(),
+            empty_channels: channels
+                .iter()
+                .filter(|c| c.data_count == 0)
+                .map(|c| c.name.clone())
+                .collect(),
+            checksum: self.verify_checksum()?,
+        })
+    }
+
+    /// Channels whose datatype we couldn't recognize, and as such can't be read
+    ///
+    /// These are collected by [LDReader::read_channels], and are also included in its result.
+    /// The iRacing mu exporter is known to produce these.
+    pub fn invalid_channels(&self) -> &[ChannelMetadata] {
+        &self.invalid_channels
+    }
+
+    /// Returns the number of bytes between the data sections of consecutive channels
+    ///
+    /// Channels are sorted by their `data_addr`, so the returned list has one entry less than
+    /// the number of channels. Overlapping data sections are reported as a 0 byte gap.
+    pub fn data_gaps(&mut self) -> I2Result<Vec<u64>> {
+        let mut channels = self.read_channels()?;
+        channels.sort_by_key(|c| c.data_addr);
+
+        Ok(channels
+            .windows(2)
+            .map(|pair| {
+                let end = pair[0].data_addr as u64 + pair[0].data_size() as u64;
+                (pair[1].data_addr as u64).saturating_sub(end)
+            })
+            .collect())
     }
 
     /// Read the [ChannelMetadata] block at file offset `addr`
-    fn read_channel_metadata(&mut self, addr: u32) -> I2Result<ChannelMetadata> {
-        self.source.seek(SeekFrom::Start(addr as u64))?;
+    ///
+    /// The size of the unknown trailer at the end of the block varies between devices. When the
+    /// next block follows this one (without this channel's data in between) we use the distance
+    /// between them to figure it out, otherwise we assume `trailer_size`. Returns the trailer size
+    /// that was used.
+    pub(crate) fn read_channel_metadata(
+        &mut self,
+        addr: FileAddr,
+        trailer_size: usize,
+    ) -> I2Result<(ChannelMetadata, usize)> {
+        self.parse_channel_metadata(addr, trailer_size)
+            .map_err(eof_context("channel metadata", addr.into()))
+    }
+
+    fn parse_channel_metadata(
+        &mut self,
+        addr: FileAddr,
+        trailer_size: usize,
+    ) -> I2Result<(ChannelMetadata, usize)> {
+        addr.seek(self.source)?;
 
         let prev_addr = self.source.read_u32::<LittleEndian>()?;
         let next_addr = self.source.read_u32::<LittleEndian>()?;
@@ -222,17 +688,34 @@ impl<'a, S: Read + Seek> LDReader<'a, S> {
 
         let sample_rate = self.source.read_u16::<LittleEndian>()?;
 
-        let offset = self.source.read_u16::<LittleEndian>()?;
-        let mul = self.source.read_u16::<LittleEndian>()?;
-        let scale = self.source.read_u16::<LittleEndian>()?;
+        let offset = self.source.read_i16::<LittleEndian>()?;
+        let mul = self.source.read_i16::<LittleEndian>()?;
+        let scale = self.source.read_i16::<LittleEndian>()?;
         let dec_places = self.source.read_i16::<LittleEndian>()?;
 
         let name = self.read_string(32)?;
         let short_name = self.read_string(8)?;
         let unit = self.read_string(12)?;
-        let _unknown = self.read_bytes(40)?; // ? (40 bytes for ACC, 32 bytes for acti)
 
-        Ok(ChannelMetadata {
+        // 40 bytes for ACC, 32 bytes for acti
+        //
+        // In Sample1.ld the first 8 bytes of this trailer hold the max and min raw sample values
+        // as i32, and the rest is zeros. We've looked for a longer channel description here, but
+        // there doesn't seem to be one.
+        let addr = addr.0;
+        let entry_size = next_addr.wrapping_sub(addr);
+        let entry_sizes = ChannelMetadata::FIXED_SIZE
+            ..=ChannelMetadata::FIXED_SIZE + ChannelMetadata::MAX_TRAILER_SIZE;
+        let data_between = data_addr > addr && data_addr < next_addr;
+        let trailer_size = if next_addr > addr && !data_between && entry_sizes.contains(&entry_size)
+        {
+            (entry_size - ChannelMetadata::FIXED_SIZE) as usize
+        } else {
+            trailer_size
+        };
+        let _unknown = self.read_bytes(trailer_size)?;
+
+        let channel = ChannelMetadata {
             prev_addr,
             next_addr,
             data_addr,
@@ -246,139 +729,945 @@ impl<'a, S: Read + Seek> LDReader<'a, S> {
             name,
             short_name,
             unit,
-        })
+        };
+        Ok((channel, trailer_size))
+    }
+
+    /// Reads the samples of a channel one at a time
+    ///
+    /// Unlike [LDReader::channel_data] this doesn't hold the whole channel in memory, samples are
+    /// read from the source on each call to `next`. The iterator stops after `data_count`
+    /// samples, or after the first error.
+    pub fn channel_data_iter(
+        &mut self,
+        channel: &ChannelMetadata,
+    ) -> I2Result<impl Iterator<Item = I2Result<Sample>> + use<'_, 'a, S>> {
+        let count = if self.has_data(channel) {
+            channel.data_count
+        } else {
+            0
+        };
+        FileAddr::from(channel.data_addr).seek(self.source)?;
+
+        let channel = channel.clone();
+        let mut failed = false;
+        Ok((0..count).map_while(move |_| {
+            if failed {
+                return None;
+            }
+            let sample = self.read_sample(&channel);
+            failed = sample.is_err();
+            Some(sample)
+        }))
     }
 
-    // TODO: We should probably have a iterator over channel data
+    /// Reads the sample at `index` of a channel, without reading any of the others
+    ///
+    /// Fails with [I2Error::SampleIndexOutOfRange] if the channel doesn't have that many samples.
+    pub fn sample_at(&mut self, channel: &ChannelMetadata, index: u32) -> I2Result<Sample> {
+        if index >= channel.data_count || !self.has_data(channel) {
+            return Err(I2Error::SampleIndexOutOfRange {
+                channel: channel.name.clone(),
+                index,
+                data_count: channel.data_count,
+            });
+        }
+
+        let offset = index as u64 * channel.datatype.size() as u64;
+        self.source
+            .seek(SeekFrom::Start(channel.data_addr as u64 + offset))?;
+        self.read_sample(channel)
+    }
 
     /// Returns a iterator over the channel data
     pub fn channel_data(&mut self, channel: &ChannelMetadata) -> I2Result<Vec<Sample>> {
-        self.source
-            .seek(SeekFrom::Start(channel.data_addr as u64))?;
+        let mut data = Vec::new();
+        self.channel_data_into(channel, &mut data)?;
+        Ok(data)
+    }
 
-        // Data for a channel is stored in a contiguous manner at the addr ptr
-        let data = (0..channel.data_count)
-            .map(|_| {
-                Ok({
-                    match channel.datatype {
-                        Datatype::Beacon16 | Datatype::I16 => {
-                            Sample::I16(self.source.read_i16::<LittleEndian>()?)
-                        }
-                        Datatype::Beacon32 | Datatype::I32 => {
-                            Sample::I32(self.source.read_i32::<LittleEndian>()?)
-                        }
+    /// Reads the channel data into `buf`, replacing its previous contents
+    ///
+    /// This allows reusing the same allocation when reading many channels.
+    pub fn channel_data_into(
+        &mut self,
+        channel: &ChannelMetadata,
+        buf: &mut Vec<Sample>,
+    ) -> I2Result<()> {
+        buf.clear();
+        self.range_warnings.clear();
+        self.partially_read = None;
+
+        // Files exported from iRacing have Invalid channels, which are fine as long as they're empty
+        if !self.has_data(channel) || channel.data_count == 0 {
+            return Ok(());
+        }
+        if channel.datatype == Datatype::Invalid {
+            return Err(I2Error::InvalidChannelData {
+                name: channel.name.clone(),
+            });
+        }
 
-                        Datatype::F16 => unimplemented!("Reading f16 samples unimplemented"),
-                        Datatype::F32 => Sample::F32(self.source.read_f32::<LittleEndian>()?),
-                        Datatype::Invalid => panic!(
-                            "Tried to read invalid datatype from channel: {}",
-                            channel.name
-                        ),
-                    }
-                })
-            })
-            .collect::<I2Result<Vec<_>>>()?;
+        if let Some(data) = self.cache.as_mut().and_then(|c| c.get(channel)) {
+            buf.extend_from_slice(data);
+            self.check_partial(channel, buf);
+            self.check_range(channel, buf);
+            return Ok(());
+        }
 
-        Ok(data)
+        // Data for a channel is stored in a contiguous manner at the addr ptr, so read it
+        // all at once and decode from the buffer
+        if let Some(bytes) = self.mapped.map(|mapped| mapped(self.source)) {
+            let data = channel_bytes(bytes, channel, self.partial_reads)?;
+            decode_samples(data, channel, buf);
+        } else if self.partial_reads {
+            FileAddr::from(channel.data_addr).seek(self.source)?;
+            // Incomplete samples at the end are ignored by decode_samples
+            self.scratch.clear();
+            (&mut self.source)
+                .take(channel.data_size() as u64)
+                .read_to_end(&mut self.scratch)?;
+            decode_samples(&self.scratch, channel, buf);
+        } else {
+            FileAddr::from(channel.data_addr).seek(self.source)?;
+            self.scratch.resize(channel.data_size() as usize, 0);
+            self.source
+                .read_exact(&mut self.scratch[..])
+                .map_err(|e| eof_context("channel data", channel.data_addr as u64)(e.into()))?;
+            decode_samples(&self.scratch, channel, buf);
+        }
+
+        if let Some(cache) = self.cache.as_mut() {
+            cache.insert(channel, buf.clone());
+        }
+
+        self.check_partial(channel, buf);
+        self.check_range(channel, buf);
+        Ok(())
     }
 
-    fn read_bytes(&mut self, size: usize) -> io::Result<Vec<u8>> {
-        let mut bytes: Vec<u8> = iter::repeat(0u8).take(size).collect();
-        self.source.read_exact(&mut bytes[0..size])?;
-        Ok(bytes)
+    /// Records a [PartiallyRead] warning if `samples` is missing some of the channel's data
+    fn check_partial(&mut self, channel: &ChannelMetadata, samples: &[Sample]) {
+        if (samples.len() as u64) < channel.data_count as u64 && self.has_data(channel) {
+            self.partially_read = Some(PartiallyRead {
+                channel: channel.name.clone(),
+                expected: channel.data_count,
+                read: samples.len() as u32,
+            });
+        }
     }
 
-    /// Reads a string with a fixed size trimming null bytes
-    fn read_string(&mut self, size: usize) -> I2Result<String> {
-        let bytes = self.read_bytes(size)?;
-        let str_size = bytes.iter().position(|c| *c == b'\0').unwrap_or(size);
-        let str = ::std::str::from_utf8(&bytes[0..str_size])?;
-        Ok(str.to_string())
+    /// Records a [RangeWarning] for every sample outside of the range check bounds
+    fn check_range(&mut self, channel: &ChannelMetadata, samples: &[Sample]) {
+        let Some(bounds) = &self.range_check else {
+            return;
+        };
+
+        self.range_warnings.extend(
+            samples
+                .iter()
+                .map(|s| s.decode_f64(channel))
+                .enumerate()
+                .filter(|(_, value)| !bounds.contains(value))
+                .map(|(index, value)| RangeWarning {
+                    channel: channel.name.clone(),
+                    index,
+                    value,
+                }),
+        );
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use crate::reader::LDReader;
-    use crate::{ChannelMetadata, Datatype, Event, Header, Sample, Vehicle, Venue};
-    use std::fs;
-    use std::io::Cursor;
+    /// Checks if `channel` points to a data section
+    ///
+    /// A 0 data pointer, either in the channel or the header, means that there is no data. We
+    /// shouldn't try to read it since that's where the header is.
+    fn has_data(&self, channel: &ChannelMetadata) -> bool {
+        let header_has_data = self.header.as_ref().is_none_or(|h| h.channel_data_ptr != 0);
+        header_has_data && channel.data_addr != 0
+    }
 
-    #[test]
-    fn read_sample1_header() {
-        let bytes = fs::read("./samples/Sample1.ld").unwrap();
-        let mut cursor = Cursor::new(bytes);
-        let mut reader = LDReader::new(&mut cursor);
+    /// Reads a single sample of `channel` at the current position of the source
+    fn read_sample(&mut self, channel: &ChannelMetadata) -> I2Result<Sample> {
+        Ok(match channel.datatype {
+            Datatype::Beacon16 | Datatype::I16 => {
+                Sample::I16(self.source.read_i16::<LittleEndian>()?)
+            }
+            Datatype::Beacon32 | Datatype::I32 => {
+                Sample::I32(self.source.read_i32::<LittleEndian>()?)
+            }
 
-        let header = reader.read_header().unwrap();
-        assert_eq!(
-            header,
-            Header {
-                channel_meta_ptr: 0x3448,
-                channel_data_ptr: 0x5A10,
-                event_ptr: 0x06E2,
-                device_serial: 0x2EE7,
-                device_type: "ADL".to_string(),
-                device_version: 0x01A4,
-                num_channels: 0x4E,
-                date_string: "23/11/2005".to_string(),
-                time_string: "09:53:00".to_string(),
-                driver: "".to_string(),
-                vehicleid: "11A".to_string(),
-                venue: "Calder".to_string(),
-                session: "2".to_string(),
-                short_comment: "second warmup".to_string(),
+            Datatype::F16 => Sample::F16(f16_to_f32(self.source.read_u16::<LittleEndian>()?)),
+            Datatype::F32 => Sample::F32(self.source.read_f32::<LittleEndian>()?),
+            Datatype::Invalid => {
+                return Err(I2Error::InvalidChannelData {
+                    name: channel.name.clone(),
+                })
             }
-        );
+        })
     }
 
-    #[test]
-    fn read_sample1_channel_metadata() {
-        let bytes = fs::read("./samples/Sample1.ld").unwrap();
-        let mut cursor = Cursor::new(bytes);
-        let mut reader = LDReader::new(&mut cursor);
+    /// Finds the time in seconds and the decoded value of the maximum value of a channel
+    ///
+    /// Samples are read one at a time, without loading the whole channel. Returns `None` if the
+    /// channel has no samples.
+    pub fn peak_time(&mut self, channel: &ChannelMetadata) -> I2Result<Option<(f64, f64)>> {
+        self.extreme_time(channel, |value, best| value > best)
+    }
 
-        let channels = reader.read_channels().unwrap();
-        assert_eq!(channels.len(), 78);
-        assert_eq!(
-            channels[0],
-            ChannelMetadata {
-                prev_addr: 0,
-                next_addr: 13508,
-                data_addr: 23056,
-                data_count: 908,
-                datatype: Datatype::I16,
-                sample_rate: 2,
-                offset: 0,
-                mul: 1,
-                scale: 1,
-                dec_places: 1,
-                name: "Air Temp Inlet".to_owned(),
-                short_name: "Air Tem".to_owned(),
-                unit: "C".to_owned(),
-            }
-        );
+    /// Finds the time in seconds and the decoded value of the minimum value of a channel
+    ///
+    /// See [LDReader::peak_time]
+    pub fn min_time(&mut self, channel: &ChannelMetadata) -> I2Result<Option<(f64, f64)>> {
+        self.extreme_time(channel, |value, best| value < best)
+    }
 
-        assert_eq!(
-            channels[1],
-            ChannelMetadata {
-                prev_addr: 13384,
-                next_addr: 13632,
-                data_addr: 24872,
-                data_count: 4540,
-                datatype: Datatype::I16,
-                sample_rate: 10,
-                offset: 0,
-                mul: 1,
-                scale: 1,
-                dec_places: 0,
-                name: "Brake Temp FL".to_owned(),
-                short_name: "Brake T".to_owned(),
-                unit: "C".to_owned(),
+    /// Returns the first sample for which `is_better` holds against all other samples
+    fn extreme_time(
+        &mut self,
+        channel: &ChannelMetadata,
+        is_better: impl Fn(f64, f64) -> bool,
+    ) -> I2Result<Option<(f64, f64)>> {
+        if !self.has_data(channel) {
+            return Ok(None);
+        }
+
+        FileAddr::from(channel.data_addr).seek(self.source)?;
+
+        let mut best: Option<(u32, f64)> = None;
+        for i in 0..channel.data_count {
+            let value = self.read_sample(channel)?.decode_f64(channel);
+            match best {
+                Some((_, best_value)) if !is_better(value, best_value) => {}
+                _ => best = Some((i, value)),
             }
-        );
+        }
 
-        assert_eq!(
+        Ok(best.map(|(i, value)| (channel.sample_time(i as usize), value)))
+    }
+
+    /// Finds where lap beacons fire in the [Datatype::Beacon16] and [Datatype::Beacon32] channels
+    ///
+    /// A beacon fires at every sample with a non-zero raw value that is different from the
+    /// previous sample. Beacons from all beacon channels are returned sorted by time, and
+    /// channels with a 0 sample rate are skipped.
+    pub fn beacons(&mut self) -> I2Result<Vec<Beacon>> {
+        let channels = self.read_channels()?;
+
+        let mut beacons = Vec::new();
+        for channel in channels
+            .iter()
+            .filter(|c| matches!(c.datatype, Datatype::Beacon16 | Datatype::Beacon32))
+        {
+            let data = self.channel_data(channel)?;
+
+            let mut previous = 0;
+            for (time_secs, sample) in channel.time_axis().zip(data.iter()) {
+                let raw = match sample {
+                    Sample::I16(v) => *v as i32,
+                    Sample::I32(v) => *v,
+                    Sample::F16(_) | Sample::F32(_) => continue,
+                };
+                if raw != 0 && raw != previous {
+                    beacons.push(Beacon { time_secs, raw });
+                }
+                previous = raw;
+            }
+        }
+
+        beacons.sort_by(|a, b| a.time_secs.total_cmp(&b.time_secs));
+        Ok(beacons)
+    }
+
+    /// Finds channels whose sample count doesn't match their sample rate, which usually means
+    /// that the logger dropped samples
+    ///
+    /// The log is assumed to last as long as its longest channel. Channels that are off by more
+    /// than `tolerance_secs` worth of samples are returned, channels with a 0 sample rate are
+    /// skipped.
+    pub fn check_sample_counts(
+        &mut self,
+        tolerance_secs: f64,
+    ) -> I2Result<Vec<SampleCountMismatch>> {
+        let channels = self.read_channels()?;
+        let duration_secs = channels
+            .iter()
+            .map(ChannelMetadata::duration_secs)
+            .fold(0.0, f64::max);
+
+        Ok(channels
+            .into_iter()
+            .filter(|c| c.sample_rate != 0)
+            .filter_map(|channel| {
+                let expected = channel.expected_samples(duration_secs);
+                let missing = expected as f64 - channel.data_count as f64;
+                let missing_secs = missing / channel.sample_rate as f64;
+                (missing_secs.abs() > tolerance_secs).then_some(SampleCountMismatch {
+                    expected,
+                    actual: channel.data_count,
+                    missing_secs,
+                    channel: channel.name,
+                })
+            })
+            .collect())
+    }
+
+    /// Splits the session into laps at each beacon, see [LDReader::beacons]
+    ///
+    /// The session ends with the longest channel. Everything before the first beacon is the
+    /// out lap, and everything after the last one is the in lap. A log without beacons is a
+    /// single out lap.
+    pub fn laps(&mut self) -> I2Result<Vec<Lap>> {
+        let end_secs = self
+            .read_channels()?
+            .iter()
+            .map(ChannelMetadata::duration_secs)
+            .fold(0.0, f64::max);
+
+        let mut boundaries = vec![0.0];
+        for beacon in self.beacons()? {
+            if beacon.time_secs > *boundaries.last().unwrap() && beacon.time_secs < end_secs {
+                boundaries.push(beacon.time_secs);
+            }
+        }
+        boundaries.push(end_secs);
+
+        let count = boundaries.len() - 1;
+        Ok(boundaries
+            .windows(2)
+            .enumerate()
+            .map(|(number, bounds)| Lap {
+                number,
+                kind: match number {
+                    0 => LapKind::Out,
+                    n if n == count - 1 => LapKind::In,
+                    _ => LapKind::Flying,
+                },
+                start_secs: bounds[0],
+                end_secs: bounds[1],
+            })
+            .collect())
+    }
+
+    /// Reads the channel data and decodes it into a [DecodedChannel]
+    pub fn decode_channel(&mut self, channel: &ChannelMetadata) -> I2Result<DecodedChannel> {
+        let data = self.channel_data(channel)?;
+
+        let values = self.decode_tables.decode(&data, channel);
+        let times = (0..data.len()).map(|i| channel.sample_time(i)).collect();
+
+        Ok(DecodedChannel {
+            name: channel.name.clone(),
+            unit: channel.unit.clone(),
+            sample_rate: channel.sample_rate,
+            values,
+            times,
+        })
+    }
+
+    /// Reads the channel data and decodes it into physical values, see [Sample::decode_f64]
+    pub fn channel_data_decoded(&mut self, channel: &ChannelMetadata) -> I2Result<Vec<f64>> {
+        let data = self.channel_data(channel)?;
+        Ok(self.decode_tables.decode(&data, channel))
+    }
+
+    /// Reads the channel list once and decodes the data of every channel, see
+    /// [LDReader::channel_data_decoded]
+    ///
+    /// The same buffer is reused for the raw samples of every channel.
+    pub fn read_all_decoded(&mut self) -> I2Result<Vec<(ChannelMetadata, Vec<f64>)>> {
+        let channels = self.read_channels()?;
+
+        let mut samples = Vec::new();
+        channels
+            .into_iter()
+            .map(|channel| {
+                self.channel_data_into(&channel, &mut samples)?;
+                let values = self.decode_tables.decode(&samples, &channel);
+                Ok((channel, values))
+            })
+            .collect()
+    }
+
+    /// Decodes the channel data and converts it to `unit`
+    ///
+    /// Fails with [I2Error::UnknownUnitConversion] if we don't know how to convert from the
+    /// channel's unit, see [crate::convert_unit].
+    pub fn channel_data_in_unit(
+        &mut self,
+        channel: &ChannelMetadata,
+        unit: &str,
+    ) -> I2Result<Vec<f64>> {
+        let unknown = || I2Error::UnknownUnitConversion {
+            from: channel.unit.clone(),
+            to: unit.to_string(),
+        };
+        convert_unit(0.0, &channel.unit, unit).ok_or_else(unknown)?;
+
+        self.channel_data(channel)?
+            .iter()
+            .map(|s| convert_unit(s.decode_f64(channel), &channel.unit, unit).ok_or_else(unknown))
+            .collect()
+    }
+
+    /// Reads the channel data and smooths the decoded values with a centered moving average
+    ///
+    /// Each value is averaged with the `window / 2` values on either side of it, near the start
+    /// and end of the channel the window shrinks to the available values.
+    pub fn channel_data_smoothed(
+        &mut self,
+        channel: &ChannelMetadata,
+        window: usize,
+    ) -> I2Result<Vec<f64>> {
+        let values = self.decode_channel(channel)?.values;
+        let half = window / 2;
+
+        // prefix[i] holds the sum of the first i values
+        let prefix: Vec<f64> = iter::once(0.0)
+            .chain(values.iter().scan(0.0, |sum, v| {
+                *sum += v;
+                Some(*sum)
+            }))
+            .collect();
+
+        Ok((0..values.len())
+            .map(|i| {
+                let start = i.saturating_sub(half);
+                let end = (i + half + 1).min(values.len());
+                (prefix[end] - prefix[start]) / (end - start) as f64
+            })
+            .collect())
+    }
+
+    /// Integrates the decoded values of a channel over time using the trapezoidal rule
+    ///
+    /// The result is in the channel's unit multiplied by seconds. Channels with a sample rate
+    /// of 0 integrate to 0.
+    pub fn integrate(&mut self, channel: &ChannelMetadata) -> I2Result<f64> {
+        if channel.sample_rate == 0 {
+            return Ok(0.0);
+        }
+
+        let values = self.decode_channel(channel)?.values;
+        let dt = 1.0 / channel.sample_rate as f64;
+        Ok(values
+            .windows(2)
+            .map(|pair| (pair[0] + pair[1]) / 2.0 * dt)
+            .sum())
+    }
+
+    /// Computes the time derivative of the decoded values of a channel
+    ///
+    /// Interior points use central differences, while the first and last points use forward
+    /// and backward differences respectively. The result is in the channel's unit per second,
+    /// channels with a single sample or a sample rate of 0 have a derivative of 0.
+    pub fn derivative(&mut self, channel: &ChannelMetadata) -> I2Result<Vec<f64>> {
+        let values = self.decode_channel(channel)?.values;
+        if values.len() < 2 || channel.sample_rate == 0 {
+            return Ok(vec![0.0; values.len()]);
+        }
+
+        let rate = channel.sample_rate as f64;
+        let last = values.len() - 1;
+        Ok((0..values.len())
+            .map(|i| match i {
+                0 => (values[1] - values[0]) * rate,
+                i if i == last => (values[last] - values[last - 1]) * rate,
+                i => (values[i + 1] - values[i - 1]) * rate / 2.0,
+            })
+            .collect())
+    }
+
+    /// Finds where sample timestamps should come from
+    ///
+    /// If the file has a channel named like [TimeBase::TIME_CHANNEL_NAMES] with a unit of
+    /// seconds, its decoded values are used as the authoritative time base, otherwise
+    /// timestamps are derived from each channel's sample rate.
+    pub fn time_base(&mut self) -> I2Result<TimeBase> {
+        let time_channel = self
+            .read_channels()?
+            .into_iter()
+            .find(|c| c.unit == "s" && TimeBase::TIME_CHANNEL_NAMES.contains(&c.name.as_str()));
+
+        Ok(match time_channel {
+            Some(channel) => {
+                let times = self.decode_channel(&channel)?.values;
+                TimeBase::Channel { channel, times }
+            }
+            None => TimeBase::SampleRate,
+        })
+    }
+
+    /// Finds the throttle, brake and steering channels and returns them on a common time base
+    ///
+    /// The time base uses the highest sample rate of the channels that were found, lower rate
+    /// channels hold their previous value until the next sample.
+    pub fn driver_inputs(&mut self) -> I2Result<DriverInputs> {
+        let channels = self.read_channels()?;
+        let find = |names: &[&str]| {
+            names
+                .iter()
+                .find_map(|name| channels.iter().find(|c| c.name == *name))
+                .cloned()
+        };
+
+        let inputs = [
+            find(DriverInputs::THROTTLE_NAMES),
+            find(DriverInputs::BRAKE_NAMES),
+            find(DriverInputs::STEERING_NAMES),
+        ];
+
+        let mut decoded = Vec::new();
+        for input in inputs.iter() {
+            decoded.push(match input {
+                Some(channel) => Some(self.decode_channel(channel)?),
+                None => None,
+            });
+        }
+
+        let rate = decoded
+            .iter()
+            .flatten()
+            .map(|c| c.sample_rate)
+            .max()
+            .unwrap_or(0);
+        let duration = decoded
+            .iter()
+            .flatten()
+            .filter(|c| c.sample_rate != 0)
+            .map(|c| c.values.len() as f64 / c.sample_rate as f64)
+            .fold(0.0, f64::max);
+        let len = (duration * rate as f64).round() as usize;
+        let times: Vec<f64> = (0..len).map(|i| i as f64 / rate as f64).collect();
+
+        let mut resampled = decoded.into_iter().map(|channel| {
+            channel.map(|c| {
+                times
+                    .iter()
+                    .map(|t| {
+                        let idx = (t * c.sample_rate as f64) as usize;
+                        c.values
+                            .get(idx.min(c.values.len().saturating_sub(1)))
+                            .copied()
+                            .unwrap_or(f64::NAN)
+                    })
+                    .collect()
+            })
+        });
+
+        Ok(DriverInputs {
+            throttle: resampled.next().flatten(),
+            brake: resampled.next().flatten(),
+            steering: resampled.next().flatten(),
+            times,
+        })
+    }
+
+    /// Checks the bytes after the last data section for a checksum
+    ///
+    /// We haven't found a checksum in any ld file so far, so this never reports a valid
+    /// checksum, it only tells apart files that end with the data section from files that
+    /// have some trailing bytes after it.
+    pub fn verify_checksum(&mut self) -> I2Result<ChecksumStatus> {
+        let channels = self.read_channels()?;
+        let data_end = channels
+            .iter()
+            .map(|c| c.data_addr as u64 + c.data_size() as u64)
+            .max()
+            .unwrap_or(0);
+
+        let file_end = self.source.seek(SeekFrom::End(0))?;
+        Ok(match file_end.saturating_sub(data_end) {
+            0 => ChecksumStatus::NoChecksum,
+            len => ChecksumStatus::UnknownTrailer { len },
+        })
+    }
+
+    /// Reads the key value pairs written with [crate::LDWriter::set_extension]
+    ///
+    /// These are stored in a block right after the last data section, which is ignored by i2.
+    /// Returns an empty list if the file doesn't have an extension block, and fails with
+    /// [I2Error::UnexpectedEof] if the block has more entries or longer strings than fit in the
+    /// rest of the file.
+    pub fn extensions(&mut self) -> I2Result<Vec<(String, String)>> {
+        let channels = self.read_channels()?;
+        let data_end = channels
+            .iter()
+            .map(|c| c.data_addr as u64 + c.data_size() as u64)
+            .max()
+            .unwrap_or(FULL_HEADER.len() as u64);
+        self.source.seek(SeekFrom::Start(data_end))?;
+
+        let mut marker = [0u8; 8];
+        match self.source.read_exact(&mut marker) {
+            Ok(()) if marker == EXTENSION_MARKER => {}
+            Ok(()) => return Ok(Vec::new()),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        }
+
+        // Counts and lengths come from the file, so check that they fit in it before allocating
+        let file_size = self.source.seek(SeekFrom::End(0))?;
+        let count_addr = data_end + marker.len() as u64;
+        self.source.seek(SeekFrom::Start(count_addr))?;
+        let count = self.source.read_u32::<LittleEndian>()?;
+        // Each entry has at least the two lengths
+        if count as u64 * 8 > file_size.saturating_sub(count_addr + 4) {
+            return Err(I2Error::UnexpectedEof {
+                while_reading: "extension block",
+                offset: count_addr,
+            });
+        }
+
+        (0..count)
+            .map(|_| {
+                let key = self.read_prefixed_string(file_size)?;
+                let value = self.read_prefixed_string(file_size)?;
+                Ok((key, value))
+            })
+            .collect()
+    }
+
+    /// Reads a string prefixed with its length in bytes as a u32
+    ///
+    /// Fails with [I2Error::UnexpectedEof] if the string doesn't fit before `file_size`
+    fn read_prefixed_string(&mut self, file_size: u64) -> I2Result<String> {
+        let len_addr = self.source.stream_position()?;
+        let len = self.source.read_u32::<LittleEndian>()?;
+        if len as u64 > file_size.saturating_sub(len_addr + 4) {
+            return Err(I2Error::UnexpectedEof {
+                while_reading: "extension block",
+                offset: len_addr,
+            });
+        }
+        let bytes = self.read_bytes(len as usize)?;
+        Ok(String::from_utf8(bytes).map_err(|e| e.utf8_error())?)
+    }
+
+    fn read_bytes(&mut self, size: usize) -> io::Result<Vec<u8>> {
+        let mut bytes: Vec<u8> = iter::repeat_n(0u8, size).collect();
+        self.source.read_exact(&mut bytes[0..size])?;
+        Ok(bytes)
+    }
+
+    /// Reads a string with a fixed size trimming null bytes
+    fn read_string(&mut self, size: usize) -> I2Result<String> {
+        let bytes = self.read_bytes(size)?;
+        let str_size = bytes.iter().position(|c| *c == b'\0').unwrap_or(size);
+        let str = ::std::str::from_utf8(&bytes[0..str_size])?;
+        Ok(str.to_string())
+    }
+}
+
+/// Decodes the samples of `channel` from its raw data section
+pub(crate) fn decode_samples(bytes: &[u8], channel: &ChannelMetadata, out: &mut Vec<Sample>) {
+    if bytes.is_empty() {
+        return;
+    }
+
+    match channel.datatype {
+        Datatype::Beacon16 | Datatype::I16 => out.extend(
+            bytes
+                .chunks_exact(2)
+                .map(|b| Sample::I16(i16::from_le_bytes([b[0], b[1]]))),
+        ),
+        Datatype::Beacon32 | Datatype::I32 => out.extend(
+            bytes
+                .chunks_exact(4)
+                .map(|b| Sample::I32(i32::from_le_bytes([b[0], b[1], b[2], b[3]]))),
+        ),
+
+        Datatype::F16 => out.extend(
+            bytes
+                .chunks_exact(2)
+                .map(|b| Sample::F16(f16_to_f32(u16::from_le_bytes([b[0], b[1]])))),
+        ),
+        Datatype::F32 => out.extend(
+            bytes
+                .chunks_exact(4)
+                .map(|b| Sample::F32(f32::from_le_bytes([b[0], b[1], b[2], b[3]]))),
+        ),
+        // We don't know the size of these, channel_data_into rejects them if they have samples
+        Datatype::Invalid => {}
+    }
+}
+
+/// Returns the data section of `channel` out of `bytes`, the whole contents of the file
+///
+/// With `partial_reads`, a data section cut short by the end of the file returns the bytes that
+/// are available, see [LDReader::with_partial_reads].
+pub(crate) fn channel_bytes<'b>(
+    bytes: &'b [u8],
+    channel: &ChannelMetadata,
+    partial_reads: bool,
+) -> I2Result<&'b [u8]> {
+    let start = (channel.data_addr as usize).min(bytes.len());
+    let end = start.saturating_add(channel.data_size() as usize);
+    match bytes.get(start..end) {
+        Some(data) => Ok(data),
+        // Incomplete samples at the end are ignored by decode_samples
+        None if partial_reads => Ok(&bytes[start..]),
+        None => Err(I2Error::UnexpectedEof {
+            while_reading: "channel data",
+            offset: channel.data_addr as u64,
+        }),
+    }
+}
+
+/// Replaces [io::ErrorKind::UnexpectedEof] errors with a [I2Error::UnexpectedEof] that says what
+/// we were reading, and where it starts
+pub(crate) fn eof_context(while_reading: &'static str, offset: u64) -> impl Fn(I2Error) -> I2Error {
+    move |e| match e {
+        I2Error::IOError(e) if e.kind() == io::ErrorKind::UnexpectedEof => I2Error::UnexpectedEof {
+            while_reading,
+            offset,
+        },
+        e => e,
+    }
+}
+
+/// Lookup tables with the decoded value of every raw [Sample::I16] value, see
+/// [DecodeTables::decode]
+///
+/// The decoded value only depends on the scaling fields of a channel, so channels that share
+/// them also share a table. Each table takes 512 KiB, so only the [DecodeTables::CAPACITY]
+/// most recently used ones are kept.
+#[derive(Debug, Default)]
+struct DecodeTables {
+    /// Most recently used tables are at the back
+    tables: VecDeque<(DecodeTableKey, Vec<f64>)>,
+}
+
+/// `offset`, `mul`, `scale` and `dec_places` of a channel
+type DecodeTableKey = (i16, i16, i16, i16);
+
+impl DecodeTables {
+    /// Number of entries in a table, one per raw value
+    const TABLE_SIZE: usize = 1 << 16;
+    /// Maximum number of tables kept, the least recently used one is evicted after that
+    const CAPACITY: usize = 4;
+
+    /// Decodes `samples` of `channel` into their physical values, see [Sample::decode_f64]
+    ///
+    /// [Sample::I16] samples are looked up in the channel's table instead of repeating the
+    /// scaling arithmetic for every sample.
+    fn decode(&mut self, samples: &[Sample], channel: &ChannelMetadata) -> Vec<f64> {
+        let is_i16 = matches!(channel.datatype, Datatype::I16 | Datatype::Beacon16);
+        let table = if is_i16 {
+            self.table(channel, samples.len())
+        } else {
+            None
+        };
+        let Some(table) = table else {
+            return samples.iter().map(|s| s.decode_f64(channel)).collect();
+        };
+
+        samples
+            .iter()
+            .map(|s| match s {
+                Sample::I16(v) => table[(*v as i32 - i16::MIN as i32) as usize],
+                s => s.decode_f64(channel),
+            })
+            .collect()
+    }
+
+    /// Returns the table for `channel`, if there's one already or if `len` samples are enough
+    /// to make up for decoding every raw value to build it
+    fn table(&mut self, channel: &ChannelMetadata, len: usize) -> Option<&[f64]> {
+        let key = (
+            channel.offset,
+            channel.mul,
+            channel.scale,
+            channel.dec_places,
+        );
+        let entry = match self.tables.iter().position(|(k, _)| *k == key) {
+            Some(pos) => self.tables.remove(pos)?,
+            None if len >= Self::TABLE_SIZE => {
+                if self.tables.len() >= Self::CAPACITY {
+                    self.tables.pop_front();
+                }
+                let table = (i16::MIN..=i16::MAX)
+                    .map(|v| Sample::I16(v).decode_f64(channel))
+                    .collect();
+                (key, table)
+            }
+            None => return None,
+        };
+        self.tables.push_back(entry);
+        self.tables.back().map(|(_, table)| &table[..])
+    }
+}
+
+/// A LRU cache of channel samples, see [SampleCache::key]
+#[derive(Debug)]
+struct SampleCache {
+    capacity: usize,
+    /// Most recently used entries are at the back
+    entries: VecDeque<(SampleCacheKey, Vec<Sample>)>,
+}
+
+/// Channels with the same data section can still have a different sample count or datatype,
+/// so all of them are needed to tell if the cached samples belong to a channel
+type SampleCacheKey = (u32, u32, Datatype);
+
+impl SampleCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    fn key(channel: &ChannelMetadata) -> SampleCacheKey {
+        (
+            channel.data_addr,
+            channel.data_count,
+            channel.datatype.clone(),
+        )
+    }
+
+    fn get(&mut self, channel: &ChannelMetadata) -> Option<&[Sample]> {
+        let key = Self::key(channel);
+        let pos = self.entries.iter().position(|(k, _)| *k == key)?;
+        let entry = self.entries.remove(pos)?;
+        self.entries.push_back(entry);
+        self.entries.back().map(|(_, data)| &data[..])
+    }
+
+    fn insert(&mut self, channel: &ChannelMetadata, data: Vec<Sample>) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() >= self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back((Self::key(channel), data));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::reader::{read_file, DecodeTables, LDReader};
+    use crate::{
+        Beacon, ChannelMetadata, ChecksumStatus, Datatype, Event, FormatVariant, Header, I2Error,
+        LDWriter, Lap, LapKind, PartiallyRead, RangeWarning, Sample, SampleCountMismatch, TimeBase,
+        Vehicle, Venue, Warning,
+    };
+    use std::fs;
+    use std::io::{Cursor, Read, Seek, SeekFrom};
+
+    #[test]
+    fn read_sample1_header() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+
+        let header = reader.read_header().unwrap();
+        assert_eq!(
+            header,
+            Header {
+                channel_meta_ptr: 0x3448,
+                channel_data_ptr: 0x5A10,
+                event_ptr: 0x06E2,
+                device_serial: 0x2EE7,
+                device_type: "ADL".to_string(),
+                device_version: 0x01A4,
+                device_flags: 0x0080,
+                num_channels: 0x4E,
+                date_string: "23/11/2005".to_string(),
+                time_string: "09:53:00".to_string(),
+                driver: "".to_string(),
+                vehicleid: "11A".to_string(),
+                venue: "Calder".to_string(),
+                session: "2".to_string(),
+                short_comment: "second warmup".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn read_converted_header() {
+        let mut bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut cursor = Cursor::new(bytes.clone());
+        let mut reader = LDReader::new(&mut cursor);
+        let expected = reader.read_header().unwrap();
+        assert_eq!(reader.format_variant().unwrap(), FormatVariant::Standard);
+
+        // Change the fields that are constant in files logged by MoTeC devices
+        bytes[64..70].copy_from_slice(&[0, 0, 0, 0, 0, 0]);
+        bytes[84..86].copy_from_slice(&[0, 0]);
+        bytes[90..94].copy_from_slice(&[0, 0, 0, 0]);
+
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+        let header = reader.read_header().unwrap();
+        assert_eq!(
+            header,
+            Header {
+                device_flags: 0,
+                ..expected
+            }
+        );
+        assert_eq!(reader.format_variant().unwrap(), FormatVariant::Converted);
+    }
+
+    #[test]
+    fn read_sample1_file() {
+        let file = read_file("./samples/Sample1.ld").unwrap();
+
+        assert_eq!(file.header.venue, "Calder");
+        assert_eq!(file.event.unwrap().name, "i2 data day");
+        assert_eq!(file.venue.unwrap().name, "Calder");
+        assert_eq!(file.vehicle.unwrap().id, "11A");
+        assert_eq!(file.channels.len(), 78);
+    }
+
+    #[test]
+    fn read_sample1_channel_metadata() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+
+        let channels = reader.read_channels().unwrap();
+        assert_eq!(channels.len(), 78);
+        assert_eq!(
+            channels[0],
+            ChannelMetadata {
+                prev_addr: 0,
+                next_addr: 13508,
+                data_addr: 23056,
+                data_count: 908,
+                datatype: Datatype::I16,
+                sample_rate: 2,
+                offset: 0,
+                mul: 1,
+                scale: 1,
+                dec_places: 1,
+                name: "Air Temp Inlet".to_owned(),
+                short_name: "Air Tem".to_owned(),
+                unit: "C".to_owned(),
+            }
+        );
+
+        assert_eq!(
+            channels[1],
+            ChannelMetadata {
+                prev_addr: 13384,
+                next_addr: 13632,
+                data_addr: 24872,
+                data_count: 4540,
+                datatype: Datatype::I16,
+                sample_rate: 10,
+                offset: 0,
+                mul: 1,
+                scale: 1,
+                dec_places: 0,
+                name: "Brake Temp FL".to_owned(),
+                short_name: "Brake T".to_owned(),
+                unit: "C".to_owned(),
+            }
+        );
+
+        assert_eq!(
             channels[77],
             ChannelMetadata {
                 prev_addr: 22808,
@@ -398,96 +1687,1377 @@ mod tests {
         );
     }
 
-    macro_rules! assert_delta {
-        ($x:expr, $y:expr, $d:expr) => {
-            if ($x - $y).abs() > $d {
-                panic!();
-            }
-        };
+    #[test]
+    fn read_sample1_channels_filtered() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+
+        let channels = reader
+            .read_channels_filtered(|c| c.sample_rate >= 10)
+            .unwrap();
+        assert_eq!(channels.len(), 46);
+        assert!(channels.iter().all(|c| c.sample_rate >= 10));
+        assert_eq!(channels[0].name, "Brake Temp FL");
+    }
+
+    #[test]
+    fn sample1_data_gaps() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+
+        // Sample1.ld has all data sections packed together
+        let gaps = reader.data_gaps().unwrap();
+        assert_eq!(gaps.len(), 77);
+        assert!(gaps.iter().all(|gap| *gap == 0));
+    }
+
+    /// [LDReader::channel_data] reads the whole data section at once, check that it matches
+    /// reading each sample individually
+    #[test]
+    fn sample1_bulk_read_parity() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+
+        let channels = reader.read_channels().unwrap();
+        for channel in channels.iter() {
+            let bulk = reader.channel_data(channel).unwrap();
+
+            reader
+                .source
+                .seek(SeekFrom::Start(channel.data_addr as u64))
+                .unwrap();
+            let slow: Vec<_> = (0..channel.data_count)
+                .map(|_| reader.read_sample(channel).unwrap())
+                .collect();
+
+            assert_eq!(bulk, slow, "channel {}", channel.name);
+        }
+    }
+
+    #[test]
+    fn sample1_channel_data_into() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+
+        let channels = reader.read_channels().unwrap();
+        let mut buf = Vec::new();
+
+        reader.channel_data_into(&channels[1], &mut buf).unwrap();
+        assert_eq!(buf.len(), 4540);
+        assert_eq!(buf, reader.channel_data(&channels[1]).unwrap());
+
+        reader.channel_data_into(&channels[0], &mut buf).unwrap();
+        assert_eq!(buf.len(), 908);
+        assert_eq!(buf, reader.channel_data(&channels[0]).unwrap());
+    }
+
+    macro_rules! assert_delta {
+        ($x:expr, $y:expr, $d:expr) => {
+            if ($x - $y).abs() > $d {
+                panic!();
+            }
+        };
+    }
+
+    #[test]
+    fn read_sample1_channel_data() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+
+        let channels = reader.read_channels().unwrap();
+        let channel = &channels[0];
+
+        let data = reader.channel_data(channel).unwrap();
+        let data: Vec<_> = data.into_iter().take(5).collect();
+
+        assert_eq!(
+            data,
+            vec![
+                Sample::I16(199),
+                Sample::I16(199),
+                Sample::I16(201),
+                Sample::I16(199),
+                Sample::I16(199),
+            ]
+        );
+
+        assert_delta!(data[0].decode_f64(channel), 19.9, 0.000001);
+        assert_delta!(data[1].decode_f64(channel), 19.9, 0.000001);
+        assert_delta!(data[2].decode_f64(channel), 20.1, 0.000001);
+        assert_delta!(data[3].decode_f64(channel), 19.9, 0.000001);
+        assert_delta!(data[4].decode_f64(channel), 19.9, 0.000001);
+    }
+
+    #[test]
+    fn read_sample1_channel_data_decoded() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+
+        let channels = reader.read_channels().unwrap();
+        let data = reader.channel_data_decoded(&channels[0]).unwrap();
+
+        assert_eq!(data.len(), channels[0].data_count as usize);
+        assert_delta!(data[0], 19.9, 0.000001);
+        assert_delta!(data[1], 19.9, 0.000001);
+        assert_delta!(data[2], 20.1, 0.000001);
+        assert_delta!(data[3], 19.9, 0.000001);
+        assert_delta!(data[4], 19.9, 0.000001);
+    }
+
+    #[test]
+    fn read_all_decoded_sample1() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+
+        let decoded = reader.read_all_decoded().unwrap();
+        assert_eq!(decoded.len(), 78);
+
+        let (air_temp, values) = &decoded[0];
+        assert_eq!(air_temp.name, "Air Temp Inlet");
+        assert_eq!(values.len(), 908);
+        assert_delta!(values[0], 19.9, 0.000001);
+        assert_delta!(values[2], 20.1, 0.000001);
+
+        for (channel, values) in decoded.iter() {
+            assert_eq!(values, &reader.channel_data_decoded(channel).unwrap());
+        }
+    }
+
+    #[test]
+    fn decode_sample1_channel() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+
+        let channels = reader.read_channels().unwrap();
+        let decoded = reader.decode_channel(&channels[0]).unwrap();
+
+        assert_eq!(decoded.name, "Air Temp Inlet");
+        assert_eq!(decoded.unit, "C");
+        assert_eq!(decoded.sample_rate, 2);
+        assert_eq!(decoded.values.len(), 908);
+        assert_eq!(decoded.times.len(), 908);
+        assert_delta!(decoded.values[2], 20.1, 0.000001);
+        assert_delta!(decoded.times[3], 1.5, 0.000001);
+
+        // Every sample is at the start of the log when there's no sample rate
+        let no_rate = ChannelMetadata {
+            sample_rate: 0,
+            ..channels[0].clone()
+        };
+        let decoded = reader.decode_channel(&no_rate).unwrap();
+        assert_eq!(decoded.values.len(), 908);
+        assert_eq!(decoded.times, vec![0.0; 908]);
+    }
+
+    #[test]
+    fn read_sample1_driver_inputs() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+
+        let inputs = reader.driver_inputs().unwrap();
+
+        // All inputs are resampled to the 20Hz of Steered Angle and Brake Pres Front
+        assert_eq!(inputs.times.len(), 9080);
+        let steering = inputs.steering.unwrap();
+        assert_eq!(steering.len(), inputs.times.len());
+        assert_eq!(inputs.throttle.unwrap().len(), inputs.times.len());
+        assert_eq!(inputs.brake.unwrap().len(), inputs.times.len());
+    }
+
+    #[test]
+    fn verify_sample1_checksum() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+
+        assert_eq!(
+            reader.verify_checksum().unwrap(),
+            ChecksumStatus::NoChecksum
+        );
+    }
+
+    #[test]
+    fn read_sample1_event() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+
+        let event = reader.read_event().unwrap();
+
+        assert_eq!(
+            event,
+            Some(Event {
+                name: "i2 data day".to_string(),
+                session: "2".to_string(),
+                comment: "Calder Park, 23/11/05, fine sunny day".to_string(),
+                venue_addr: 0x1336,
+            })
+        );
+    }
+
+    #[test]
+    fn read_sample1_venue() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+
+        let venue = reader.read_venue().unwrap();
+
+        assert_eq!(
+            venue,
+            Some(Venue {
+                name: "Calder".to_string(),
+                raw_extra: vec![0u8; 1034],
+                vehicle_addr: 0x1F54,
+            })
+        );
+    }
+
+    #[test]
+    fn read_sample1_vehicle() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+
+        let vehicle = reader.read_vehicle().unwrap();
+
+        assert_eq!(
+            vehicle,
+            Some(Vehicle {
+                id: "11A".to_string(),
+                weight: 0,
+                _type: "Car".to_string(),
+                comment: "".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn sample1_peak_and_min_time() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+
+        let channels = reader.read_channels().unwrap();
+        let rpm = &channels[52];
+        assert_eq!(rpm.name, "Engine RPM");
+        let duration = rpm.data_count as f64 / rpm.sample_rate as f64;
+
+        let values = reader.decode_channel(rpm).unwrap().values;
+        let max = values.iter().copied().fold(f64::MIN, f64::max);
+        let min = values.iter().copied().fold(f64::MAX, f64::min);
+
+        let (peak_time, peak) = reader.peak_time(rpm).unwrap().unwrap();
+        assert!(peak_time >= 0.0 && peak_time < duration);
+        assert_eq!(peak, max);
+
+        let (min_time, min_value) = reader.min_time(rpm).unwrap().unwrap();
+        assert!(min_time >= 0.0 && min_time < duration);
+        assert_eq!(min_value, min);
+
+        let no_rate = ChannelMetadata {
+            sample_rate: 0,
+            ..rpm.clone()
+        };
+        assert_eq!(reader.peak_time(&no_rate).unwrap().unwrap(), (0.0, max));
+    }
+
+    #[test]
+    fn sample1_channel_data_smoothed() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+
+        let channels = reader.read_channels().unwrap();
+        let values = reader.decode_channel(&channels[0]).unwrap().values;
+        let smoothed = reader.channel_data_smoothed(&channels[0], 5).unwrap();
+        assert_eq!(smoothed.len(), values.len());
+
+        // The first value only has the 2 values after it in its window
+        assert_delta!(smoothed[0], (19.9 + 19.9 + 20.1) / 3.0, 0.000001);
+        let last = values.len() - 1;
+        let expected = (values[last] + values[last - 1] + values[last - 2]) / 3.0;
+        assert_delta!(smoothed[last], expected, 0.000001);
+
+        let unsmoothed = reader.channel_data_smoothed(&channels[0], 1).unwrap();
+        for (a, b) in unsmoothed.iter().zip(values.iter()) {
+            assert_delta!(a, b, 0.000001);
+        }
+    }
+
+    /// Counts the number of reads issued to the underlying source
+    struct CountingSource<S> {
+        inner: S,
+        reads: usize,
+    }
+
+    impl<S: Read> Read for CountingSource<S> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.reads += 1;
+            self.inner.read(buf)
+        }
+    }
+
+    impl<S: Seek> Seek for CountingSource<S> {
+        fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    #[test]
+    fn cached_channel_data() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut source = CountingSource {
+            inner: Cursor::new(bytes),
+            reads: 0,
+        };
+        let mut reader = LDReader::new(&mut source).with_cache(4);
+
+        let channels = reader.read_channels().unwrap();
+        let first = reader.channel_data(&channels[0]).unwrap();
+        let reads = reader.source.reads;
+
+        let second = reader.channel_data(&channels[0]).unwrap();
+        assert_eq!(reader.source.reads, reads);
+        assert_eq!(first, second);
+
+        // Uncached channels still hit the source
+        reader.channel_data(&channels[1]).unwrap();
+        assert!(reader.source.reads > reads);
+    }
+
+    #[test]
+    fn cached_channel_data_shared_addr() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor).with_cache(4);
+
+        let channels = reader.read_channels().unwrap();
+        let full = reader.channel_data(&channels[0]).unwrap();
+        assert_eq!(full.len(), 908);
+
+        // Same data section, but fewer samples
+        let shorter = ChannelMetadata {
+            data_count: 10,
+            ..channels[0].clone()
+        };
+        assert_eq!(reader.channel_data(&shorter).unwrap(), full[..10]);
+
+        // Same data section, but read as a different datatype
+        let wider = ChannelMetadata {
+            datatype: Datatype::I32,
+            data_count: 2,
+            ..channels[0].clone()
+        };
+        let raw = |i: usize| match full[i] {
+            Sample::I16(v) => v as u16 as i32,
+            _ => unreachable!(),
+        };
+        assert_eq!(
+            reader.channel_data(&wider).unwrap(),
+            [
+                Sample::I32(raw(0) | (raw(1) << 16)),
+                Sample::I32(raw(2) | (raw(3) << 16))
+            ]
+        );
+
+        let empty = ChannelMetadata {
+            data_count: 0,
+            ..channels[0].clone()
+        };
+        assert_eq!(reader.channel_data(&empty).unwrap(), []);
+    }
+
+    fn sample_header() -> Header {
+        Header {
+            channel_meta_ptr: 13384,
+            channel_data_ptr: 23056,
+            event_ptr: 1762,
+            device_serial: 12007,
+            device_type: "ADL".to_string(),
+            device_version: 420,
+            device_flags: 0x0080,
+            num_channels: 2,
+            date_string: "23/11/2005".to_string(),
+            time_string: "09:53:00".to_string(),
+            driver: "".to_string(),
+            vehicleid: "11A".to_string(),
+            venue: "Calder".to_string(),
+            session: "2".to_string(),
+            short_comment: "second warmup".to_string(),
+        }
+    }
+
+    fn sample_channel(name: &str) -> ChannelMetadata {
+        ChannelMetadata {
+            prev_addr: 0,
+            next_addr: 0,
+            data_addr: 0,
+            data_count: 0,
+            datatype: Datatype::I16,
+            sample_rate: 10,
+            offset: 0,
+            mul: 1,
+            scale: 1,
+            dec_places: 0,
+            name: name.to_string(),
+            short_name: name.to_string(),
+            unit: "".to_string(),
+        }
+    }
+
+    #[test]
+    fn integrate_constant_channel() {
+        let mut cursor = Cursor::new(Vec::new());
+        let mut writer = LDWriter::new(&mut cursor, sample_header());
+        writer.log("Power", "kW", 10, &[5.0; 21]).unwrap();
+
+        let mut reader = LDReader::new(&mut cursor);
+        let channels = reader.read_channels().unwrap();
+
+        // 21 samples at 10Hz span 2 seconds
+        assert_delta!(reader.integrate(&channels[0]).unwrap(), 10.0, 0.000001);
+    }
+
+    #[test]
+    fn derivative_of_ramp() {
+        let ramp: Vec<f64> = (0..20).map(|i| i as f64 * 1.5).collect();
+
+        let mut cursor = Cursor::new(Vec::new());
+        let mut writer = LDWriter::new(&mut cursor, sample_header());
+        writer.log("Ground Speed", "m/s", 10, &ramp).unwrap();
+
+        let mut reader = LDReader::new(&mut cursor);
+        let channels = reader.read_channels().unwrap();
+
+        let derivative = reader.derivative(&channels[0]).unwrap();
+        assert_eq!(derivative.len(), ramp.len());
+        for value in derivative {
+            assert_delta!(value, 15.0, 0.000001);
+        }
+    }
+
+    #[test]
+    fn sample1_time_base() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+
+        // Sample1.ld only has lap times, which are not a time base
+        assert_eq!(reader.time_base().unwrap(), TimeBase::SampleRate);
+        let channels = reader.read_channels().unwrap();
+        let times = TimeBase::SampleRate.times(&channels[0]);
+        assert_eq!(times.len(), 908);
+        assert_delta!(times[3], 1.5, 0.000001);
+    }
+
+    #[test]
+    fn explicit_time_base() {
+        let time: Vec<f64> = (0..10).map(|i| 100.0 + i as f64 * 0.1).collect();
+
+        let mut cursor = Cursor::new(Vec::new());
+        let mut writer = LDWriter::new(&mut cursor, sample_header());
+        writer.log("Time", "s", 10, &time).unwrap();
+        writer.log("Engine Temp", "C", 5, &[90.0; 5]).unwrap();
+
+        let mut reader = LDReader::new(&mut cursor);
+        let channels = reader.read_channels().unwrap();
+
+        let time_base = reader.time_base().unwrap();
+        assert!(matches!(&time_base, TimeBase::Channel { channel, .. } if channel.name == "Time"));
+
+        let times = time_base.times(&channels[1]);
+        assert_eq!(times.len(), 5);
+        assert_delta!(times[0], 100.0, 0.000001);
+        assert_delta!(times[1], 100.2, 0.000001);
+        assert_delta!(times[4], 100.8, 0.000001);
+    }
+
+    #[test]
+    fn sample1_channel_count() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+
+        assert_eq!(reader.channel_count().unwrap(), 78);
+    }
+
+    #[test]
+    fn channel_count_cycle() {
+        let mut cursor = Cursor::new(Vec::new());
+        LDWriter::new(&mut cursor, sample_header())
+            .with_channel(sample_channel("Air Temp Inlet"), vec![])
+            .with_channel(sample_channel("Brake Temp FL"), vec![])
+            .write()
+            .unwrap();
+
+        // Point the second channel back at the first one
+        let mut bytes = cursor.into_inner();
+        let meta = 0x3448;
+        bytes[meta + 124 + 4..meta + 124 + 8].copy_from_slice(&(meta as u32).to_le_bytes());
+
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+        assert!(matches!(
+            reader.channel_count(),
+            Err(I2Error::ChannelListCycle { at: 0x3448 })
+        ));
+    }
+
+    #[test]
+    fn read_channels_cycle() {
+        let mut cursor = Cursor::new(Vec::new());
+        LDWriter::new(&mut cursor, sample_header())
+            .with_channel(sample_channel("Air Temp Inlet"), vec![])
+            .with_channel(sample_channel("Brake Temp FL"), vec![])
+            .with_channel(sample_channel("Brake Temp FR"), vec![])
+            .write()
+            .unwrap();
+
+        // Point the last channel back at the second one
+        let mut bytes = cursor.into_inner();
+        let meta = 0x3448;
+        let second = (meta + 124) as u32;
+        bytes[meta + 2 * 124 + 4..meta + 2 * 124 + 8].copy_from_slice(&second.to_le_bytes());
+
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+        assert!(matches!(
+            reader.read_channels(),
+            Err(I2Error::ChannelListCycle { at }) if at == second
+        ));
+        assert!(matches!(
+            reader.read_file(),
+            Err(I2Error::ChannelListCycle { at }) if at == second
+        ));
+    }
+
+    #[test]
+    fn validate_sample1() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+
+        assert_eq!(reader.validate().unwrap(), []);
+    }
+
+    #[test]
+    fn validate_out_of_bounds_data() {
+        let mut bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let file_size = bytes.len() as u32;
+
+        // Point the data of the second channel at the end of the file
+        let data_addr = 0x3448 + 124 + 8;
+        bytes[data_addr..data_addr + 4].copy_from_slice(&file_size.to_le_bytes());
+        let event_ptr = 36;
+        bytes[event_ptr..event_ptr + 4].copy_from_slice(&(file_size + 1).to_le_bytes());
+
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+        assert_eq!(
+            reader.validate().unwrap(),
+            [
+                Warning::PointerOutOfBounds {
+                    pointer: "event_ptr",
+                    addr: file_size + 1,
+                    file_size: file_size as u64,
+                },
+                Warning::ChannelDataOutOfBounds {
+                    channel: "Brake Temp FL".to_string(),
+                    data_addr: file_size,
+                    data_size: 4540 * 2,
+                    file_size: file_size as u64,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_bad_channel_entries() {
+        let mut bytes = fs::read("./samples/Sample1.ld").unwrap();
+
+        // Unknown datatype code for the first channel, and a non utf8 name for the second
+        let first = 0x3448;
+        bytes[first + 18..first + 20].copy_from_slice(&42u16.to_le_bytes());
+        let second = first + 124;
+        bytes[second + 32] = 0xFF;
+
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+        assert_eq!(
+            reader.validate().unwrap(),
+            [
+                Warning::UnrecognizedDatatype {
+                    addr: first as u32,
+                    _type: 42,
+                    size: 2,
+                },
+                Warning::NonUtf8String {
+                    addr: second as u32
+                },
+            ]
+        );
+        // The rest of the list is still walked
+        assert_eq!(reader.channel_count().unwrap(), 78);
     }
 
     #[test]
-    fn read_sample1_channel_data() {
+    fn validate_broken_files() {
+        let mut cursor = Cursor::new(Vec::new());
+        LDWriter::new(&mut cursor, sample_header())
+            .with_channel(sample_channel("Air Temp Inlet"), vec![])
+            .with_channel(sample_channel("Brake Temp FL"), vec![])
+            .write()
+            .unwrap();
+        let bytes = cursor.into_inner();
+
+        // Point the second channel back at the first one
+        let mut cycle = bytes.clone();
+        let meta = 0x3448;
+        cycle[meta + 124 + 4..meta + 124 + 8].copy_from_slice(&(meta as u32).to_le_bytes());
+        let mut cursor = Cursor::new(cycle);
+        assert_eq!(
+            LDReader::new(&mut cursor).validate().unwrap(),
+            [Warning::ChannelListCycle { at: 0x3448 }]
+        );
+
+        let mut truncated = bytes.clone();
+        truncated.truncate(meta + 124 + 100);
+        let mut cursor = Cursor::new(truncated);
+        let file_size = meta as u64 + 224;
+        let data_addr = meta as u32 + 2 * 124;
+        assert_eq!(
+            LDReader::new(&mut cursor).validate().unwrap(),
+            [
+                Warning::PointerOutOfBounds {
+                    pointer: "channel_data_ptr",
+                    addr: data_addr,
+                    file_size,
+                },
+                Warning::ChannelDataOutOfBounds {
+                    channel: "Air Temp Inlet".to_string(),
+                    data_addr,
+                    data_size: 0,
+                    file_size,
+                },
+                Warning::MetadataOutOfBounds {
+                    addr: meta as u32 + 124,
+                    file_size,
+                },
+            ]
+        );
+
+        let mut cursor = Cursor::new(bytes[..100].to_vec());
+        assert_eq!(
+            LDReader::new(&mut cursor).validate().unwrap(),
+            [Warning::TruncatedHeader]
+        );
+
+        let mut cursor = Cursor::new(vec![0u8; 8]);
+        assert_eq!(
+            LDReader::new(&mut cursor).validate().unwrap(),
+            [Warning::InvalidHeaderMarker { found: 0 }]
+        );
+    }
+
+    #[test]
+    fn sample1_channel_by_index() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+
+        let channels = reader.read_channels().unwrap();
+        assert_eq!(reader.channel(3).unwrap().as_ref(), Some(&channels[3]));
+        assert_eq!(reader.channel(0).unwrap().as_ref(), Some(&channels[0]));
+        assert_eq!(reader.channel(77).unwrap().as_ref(), Some(&channels[77]));
+        assert_eq!(reader.channel(78).unwrap(), None);
+        assert_eq!(reader.channel(1000).unwrap(), None);
+    }
+
+    #[test]
+    fn sample1_sample_at() {
         let bytes = fs::read("./samples/Sample1.ld").unwrap();
         let mut cursor = Cursor::new(bytes);
         let mut reader = LDReader::new(&mut cursor);
 
         let channels = reader.read_channels().unwrap();
+        for channel in channels.iter() {
+            let data = reader.channel_data(channel).unwrap();
+            assert_eq!(reader.sample_at(channel, 2).unwrap(), data[2]);
+            let last = channel.data_count - 1;
+            assert_eq!(
+                reader.sample_at(channel, last).unwrap(),
+                data[last as usize]
+            );
+        }
+
         let channel = &channels[0];
+        assert!(matches!(
+            reader.sample_at(channel, channel.data_count),
+            Err(I2Error::SampleIndexOutOfRange { index, data_count, .. })
+                if index == channel.data_count && data_count == channel.data_count
+        ));
+    }
 
-        let data = reader.channel_data(channel).unwrap();
-        let data: Vec<_> = data.into_iter().take(5).collect();
+    #[test]
+    fn strict_channel_count() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut cursor = Cursor::new(bytes.clone());
+        let mut reader = LDReader::new(&mut cursor).with_strict(true);
+        assert_eq!(reader.read_channels().unwrap().len(), 78);
+
+        // Claim that there are 80 channels
+        let mut bytes = bytes;
+        bytes[86..90].copy_from_slice(&80u32.to_le_bytes());
+
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+        assert_eq!(reader.read_channels().unwrap().len(), 78);
+
+        let mut reader = reader.with_strict(true);
+        assert!(matches!(
+            reader.read_channels(),
+            Err(I2Error::ChannelCountMismatch {
+                header: 80,
+                found: 78
+            })
+        ));
+    }
+
+    #[test]
+    fn read_invalid_channel_data() {
+        let mut cursor = Cursor::new(Vec::new());
+        LDWriter::new(&mut cursor, sample_header())
+            .with_channel(sample_channel("Air Temp Inlet"), vec![Sample::I16(1); 4])
+            .write()
+            .unwrap();
+
+        // iRacing exports have channels with a (0, 5) type and size, but no samples
+        let mut bytes = cursor.into_inner();
+        let meta = 0x3448;
+        bytes[meta + 18..meta + 22].copy_from_slice(&[0, 0, 5, 0]);
+        bytes[meta + 12..meta + 16].copy_from_slice(&0u32.to_le_bytes());
+
+        let mut cursor = Cursor::new(bytes.clone());
+        let mut reader = LDReader::new(&mut cursor);
+        let channels = reader.read_channels().unwrap();
+        assert_eq!(channels[0].datatype, Datatype::Invalid);
+        assert_eq!(reader.channel_data(&channels[0]).unwrap(), vec![]);
+        assert_eq!(reader.channel_data_iter(&channels[0]).unwrap().count(), 0);
+
+        // Without knowing the sample size we can't read them
+        bytes[meta + 12..meta + 16].copy_from_slice(&4u32.to_le_bytes());
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+        let channels = reader.read_channels().unwrap();
+        assert!(matches!(
+            reader.channel_data(&channels[0]),
+            Err(I2Error::InvalidChannelData { name }) if name == "Air Temp Inlet"
+        ));
+    }
+
+    #[test]
+    fn read_truncated_sample1() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let truncated = |len: usize| Cursor::new(bytes[..len].to_vec());
+
+        for len in [0, 2, 100, 1000, 1761] {
+            let mut cursor = truncated(len);
+            let mut reader = LDReader::new(&mut cursor);
+            assert!(matches!(
+                reader.read_header(),
+                Err(I2Error::UnexpectedEof {
+                    while_reading: "header",
+                    offset: 0
+                })
+            ));
+        }
+
+        // In the middle of the metadata of the third channel
+        let mut cursor = truncated(0x3448 + 2 * 124 + 50);
+        let mut reader = LDReader::new(&mut cursor);
+        assert!(reader.read_header().is_ok());
+        assert!(matches!(
+            reader.read_channels(),
+            Err(I2Error::UnexpectedEof {
+                while_reading: "channel metadata",
+                offset: 0x3540
+            })
+        ));
+
+        // In the middle of the data of the first channel
+        let mut cursor = Cursor::new(bytes.clone());
+        let channels = LDReader::new(&mut cursor).read_channels().unwrap();
+        let first = channels.iter().min_by_key(|c| c.data_addr).unwrap();
+
+        let mut cursor = truncated(first.data_addr as usize + 10);
+        let mut reader = LDReader::new(&mut cursor);
+        let channels = reader.read_channels().unwrap();
+        let offset = first.data_addr as u64;
+        assert!(matches!(
+            reader.channel_data(&channels[0]),
+            Err(I2Error::UnexpectedEof {
+                while_reading: "channel data",
+                offset: o
+            }) if o == offset
+        ));
+    }
+
+    /// Some devices use a 32 byte trailer in the channel metadata instead of 40
+    #[test]
+    fn read_short_channel_metadata() {
+        let mut cursor = Cursor::new(Vec::new());
+        LDWriter::new(&mut cursor, sample_header())
+            .with_channel(sample_channel("Air Temp Inlet"), vec![])
+            .with_channel(sample_channel("Brake Temp FL"), vec![])
+            .write()
+            .unwrap();
+        let bytes = cursor.into_inner();
+
+        // Rebuild the file with 116 byte entries, ending right after the last one
+        let meta = 0x3448;
+        let entry_size = 116;
+        let mut short = bytes[..meta].to_vec();
+        short.extend_from_slice(&bytes[meta..meta + entry_size]);
+        short.extend_from_slice(&bytes[meta + 124..meta + 124 + entry_size]);
+        let next_addr = ((meta + entry_size) as u32).to_le_bytes();
+        short[meta + 4..meta + 8].copy_from_slice(&next_addr);
+
+        let mut cursor = Cursor::new(short);
+        let mut reader = LDReader::new(&mut cursor);
+        let channels = reader.read_channels().unwrap();
+        assert_eq!(channels.len(), 2);
+        assert_eq!(channels[0].name, "Air Temp Inlet");
+        assert_eq!(channels[1].name, "Brake Temp FL");
+        assert_eq!(channels[1].prev_addr, meta as u32);
+    }
+
+    #[test]
+    fn reports_invalid_channels() {
+        let mut cursor = Cursor::new(Vec::new());
+        LDWriter::new(&mut cursor, sample_header())
+            .with_channel(sample_channel("Damper Pos FL"), vec![Sample::I16(1)])
+            .with_channel(sample_channel("Damper Pos RR"), vec![])
+            .write()
+            .unwrap();
+
+        // Patch the datatype of the second channel to what the iRacing mu exporter writes
+        let mut bytes = cursor.into_inner();
+        let datatype_addr = 0x3448 + 124 + 18;
+        bytes[datatype_addr..datatype_addr + 4].copy_from_slice(&[0, 0, 5, 0]);
+
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+        let channels = reader.read_channels().unwrap();
+        assert_eq!(channels.len(), 2);
+
+        let invalid: Vec<_> = reader
+            .invalid_channels()
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect();
+        assert_eq!(invalid, ["Damper Pos RR"]);
+    }
 
+    #[test]
+    fn range_check_flags_outliers() {
+        let mut samples = vec![Sample::I16(20); 10];
+        // A flipped high bit turns 20 into 16404
+        samples[6] = Sample::I16(20 | 0x4000);
+
+        let mut cursor = Cursor::new(Vec::new());
+        LDWriter::new(&mut cursor, sample_header())
+            .with_channel(sample_channel("Air Temp Inlet"), samples)
+            .write()
+            .unwrap();
+
+        let mut reader = LDReader::new(&mut cursor).with_range_check(-40.0..=150.0);
+        let channels = reader.read_channels().unwrap();
+        let data = reader.channel_data(&channels[0]).unwrap();
+        assert_eq!(data.len(), 10);
         assert_eq!(
-            data,
-            vec![
-                Sample::I16(199),
-                Sample::I16(199),
-                Sample::I16(201),
-                Sample::I16(199),
-                Sample::I16(199),
-            ]
+            reader.range_warnings(),
+            [RangeWarning {
+                channel: "Air Temp Inlet".to_string(),
+                index: 6,
+                value: 16404.0,
+            }]
         );
+    }
 
-        assert_delta!(data[0].decode_f64(channel), 19.9, 0.000001);
-        assert_delta!(data[1].decode_f64(channel), 19.9, 0.000001);
-        assert_delta!(data[2].decode_f64(channel), 20.1, 0.000001);
-        assert_delta!(data[3].decode_f64(channel), 19.9, 0.000001);
-        assert_delta!(data[4].decode_f64(channel), 19.9, 0.000001);
+    #[test]
+    fn channel_data_in_mph() {
+        let mut cursor = Cursor::new(Vec::new());
+        let mut writer = LDWriter::new(&mut cursor, sample_header());
+        writer
+            .log("Ground Speed", "km/h", 10, &[0.0, 100.0, 160.9344])
+            .unwrap();
+
+        let mut reader = LDReader::new(&mut cursor);
+        let channels = reader.read_channels().unwrap();
+        let mph = reader.channel_data_in_unit(&channels[0], "mph").unwrap();
+        assert_eq!(mph.len(), 3);
+        assert_delta!(mph[0], 0.0, 0.000001);
+        assert_delta!(mph[1], 62.137119, 0.000001);
+        assert_delta!(mph[2], 100.0, 0.000001);
+
+        assert!(matches!(
+            reader.channel_data_in_unit(&channels[0], "C"),
+            Err(I2Error::UnknownUnitConversion { .. })
+        ));
     }
 
     #[test]
-    fn read_sample1_event() {
-        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+    fn zero_data_pointer() {
+        let mut cursor = Cursor::new(Vec::new());
+        LDWriter::new(&mut cursor, sample_header())
+            .with_channel(sample_channel("Air Temp Inlet"), vec![Sample::I16(1); 4])
+            .write()
+            .unwrap();
+
+        // Clear the channel's data_addr, keeping its data_count
+        let mut bytes = cursor.into_inner();
+        bytes[0x3448 + 8..0x3448 + 12].copy_from_slice(&[0; 4]);
+
         let mut cursor = Cursor::new(bytes);
         let mut reader = LDReader::new(&mut cursor);
+        let channels = reader.read_channels().unwrap();
+        assert_eq!(channels[0].data_count, 4);
+        assert_eq!(reader.channel_data(&channels[0]).unwrap(), vec![]);
+        assert_eq!(reader.peak_time(&channels[0]).unwrap(), None);
+    }
 
-        let event = reader.read_event().unwrap();
+    #[test]
+    fn zero_header_data_pointer() {
+        let mut cursor = Cursor::new(Vec::new());
+        LDWriter::new(&mut cursor, sample_header())
+            .with_channel(sample_channel("Air Temp Inlet"), vec![Sample::I16(1); 4])
+            .write()
+            .unwrap();
+
+        let mut bytes = cursor.into_inner();
+        bytes[12..16].copy_from_slice(&[0; 4]);
+
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+        let channels = reader.read_channels().unwrap();
+        assert_eq!(reader.channel_data(&channels[0]).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn partial_read_of_truncated_file() {
+        let samples: Vec<Sample> = (0..10).map(Sample::I16).collect();
+        let mut cursor = Cursor::new(Vec::new());
+        LDWriter::new(&mut cursor, sample_header())
+            .with_channel(sample_channel("Air Temp Inlet"), samples)
+            .write()
+            .unwrap();
+
+        // Cut the file in the middle of the 8th sample
+        let mut bytes = cursor.into_inner();
+        bytes.truncate(bytes.len() - 5);
+
+        let mut cursor = Cursor::new(bytes.clone());
+        let mut reader = LDReader::new(&mut cursor);
+        let channels = reader.read_channels().unwrap();
+        assert!(reader.channel_data(&channels[0]).is_err());
 
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor).with_partial_reads();
+        let data = reader.channel_data(&channels[0]).unwrap();
+        assert_eq!(data, (0..7).map(Sample::I16).collect::<Vec<_>>());
         assert_eq!(
-            event,
-            Some(Event {
-                name: "i2 data day".to_string(),
-                session: "2".to_string(),
-                comment: "Calder Park, 23/11/05, fine sunny day".to_string(),
-                venue_addr: 0x1336,
+            reader.partially_read(),
+            Some(&PartiallyRead {
+                channel: "Air Temp Inlet".to_string(),
+                expected: 10,
+                read: 7,
             })
         );
     }
 
     #[test]
-    fn read_sample1_venue() {
+    fn sample1_time_series() {
         let bytes = fs::read("./samples/Sample1.ld").unwrap();
         let mut cursor = Cursor::new(bytes);
         let mut reader = LDReader::new(&mut cursor);
 
-        let venue = reader.read_venue().unwrap();
+        let file = reader.read_file().unwrap();
+        let channels = file.channels.clone();
+        let set = file.into_time_series(&mut reader).unwrap();
+
+        assert_eq!(set.series.len(), channels.len());
+        for (series, channel) in set.series.iter().zip(channels.iter()) {
+            assert_eq!(series.name, channel.name);
+            assert_eq!(series.values.len(), channel.data_count as usize);
+            assert_eq!(series.times.len(), series.values.len());
+        }
+    }
+
+    #[test]
+    fn sample1_channels_grouped_by_rate() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+
+        let groups = reader.channels_grouped_by_rate().unwrap();
+        for rate in [2, 10, 20] {
+            assert!(!groups[&rate].is_empty());
+            assert!(groups[&rate].iter().all(|c| c.sample_rate == rate));
+        }
+        assert_eq!(groups[&2][0].name, "Air Temp Inlet");
+        assert_eq!(groups.values().map(Vec::len).sum::<usize>(), 78);
+    }
+
+    #[test]
+    fn decode_table_matches_arithmetic() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+
+        let mut tables = DecodeTables::default();
+        for channel in reader.read_channels().unwrap() {
+            let data = reader.channel_data(&channel).unwrap();
+            let expected: Vec<f64> = data.iter().map(|s| s.decode_f64(&channel)).collect();
+            assert_eq!(tables.decode(&data, &channel), expected, "{}", channel.name);
+        }
+        // None of the channels are long enough to be worth building a table for
+        assert!(tables.tables.is_empty());
+
+        // Every raw value, including the extremes
+        let channel = ChannelMetadata {
+            offset: -40,
+            mul: 3,
+            scale: 7,
+            dec_places: 2,
+            ..sample_channel("Steered Angle")
+        };
+        let data: Vec<Sample> = (i16::MIN..=i16::MAX).map(Sample::I16).collect();
+        let expected: Vec<f64> = data.iter().map(|s| s.decode_f64(&channel)).collect();
+        assert_eq!(tables.decode(&data, &channel), expected);
+        assert_eq!(tables.tables.len(), 1);
+
+        // Short channels only use a table if it already exists
+        assert_eq!(tables.decode(&data[..10], &channel), expected[..10]);
+        let other = ChannelMetadata {
+            dec_places: 1,
+            ..channel.clone()
+        };
+        tables.decode(&data[..10], &other);
+        assert_eq!(tables.tables.len(), 1);
+
+        // Only the most recently used tables are kept
+        for dec_places in 0..DecodeTables::CAPACITY as i16 + 2 {
+            let channel = ChannelMetadata {
+                dec_places,
+                ..sample_channel("Steered Angle")
+            };
+            let expected: Vec<f64> = data.iter().map(|s| s.decode_f64(&channel)).collect();
+            assert_eq!(tables.decode(&data, &channel), expected);
+            assert!(tables.tables.len() <= DecodeTables::CAPACITY);
+        }
+        let kept: Vec<i16> = tables.tables.iter().map(|(key, _)| key.3).collect();
+        assert_eq!(kept, [2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn lists_empty_channels() {
+        let mut cursor = Cursor::new(Vec::new());
+        LDWriter::new(&mut cursor, sample_header())
+            .with_channel(sample_channel("Damper Pos FL"), vec![])
+            .with_channel(sample_channel("Ground Speed"), vec![Sample::I16(1)])
+            .with_channel(sample_channel("Damper Pos RR"), vec![])
+            .write()
+            .unwrap();
+
+        let mut reader = LDReader::new(&mut cursor);
+        let empty: Vec<_> = reader
+            .empty_channels()
+            .unwrap()
+            .into_iter()
+            .map(|c| c.name)
+            .collect();
+        assert_eq!(empty, ["Damper Pos FL", "Damper Pos RR"]);
+    }
+
+    #[test]
+    fn read_f16_channel() {
+        let halves: [u16; 4] = [0x3C00, 0x4D40, 0xC100, 0x0001];
+        let mut cursor = Cursor::new(Vec::new());
+        LDWriter::new(&mut cursor, sample_header())
+            .with_channel(
+                sample_channel("Fuel Level"),
+                halves.iter().map(|h| Sample::I16(*h as i16)).collect(),
+            )
+            .write()
+            .unwrap();
+
+        // Patch the datatype to a 2 byte float
+        let mut bytes = cursor.into_inner();
+        let datatype_addr = 0x3448 + 18;
+        bytes[datatype_addr..datatype_addr + 4].copy_from_slice(&[7, 0, 2, 0]);
+
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+        let channels = reader.read_channels().unwrap();
+        assert_eq!(channels[0].datatype, Datatype::F16);
+
+        let data = reader.channel_data(&channels[0]).unwrap();
+        let values: Vec<f64> = data.iter().map(|s| s.decode_f64(&channels[0])).collect();
+        assert_delta!(values[0], 1.0, 0.000001);
+        assert_delta!(values[1], 21.0, 0.000001);
+        assert_delta!(values[2], -2.5, 0.000001);
+        assert_delta!(values[3], 0.0000000596, 0.0000000001);
+
+        assert_eq!(reader.peak_time(&channels[0]).unwrap(), Some((0.1, 21.0)));
+    }
+
+    #[test]
+    fn sample1_beacons() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
 
+        // Each beacon in Sample1.ld spans a few samples with different values, so they all fire
+        let beacons = reader.beacons().unwrap();
+        assert_eq!(beacons.len(), 68);
         assert_eq!(
-            venue,
-            Some(Venue {
-                name: "Calder".to_string(),
-                vehicle_addr: 0x1F54,
-            })
+            beacons[0],
+            Beacon {
+                time_secs: 44.0,
+                raw: -8725
+            }
         );
+        assert!(beacons.windows(2).all(|w| w[0].time_secs < w[1].time_secs));
     }
 
     #[test]
-    fn read_sample1_vehicle() {
+    fn written_beacons() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let header = LDReader::new(&mut Cursor::new(bytes))
+            .read_header()
+            .unwrap();
+
+        let mut cursor = Cursor::new(Vec::new());
+        let mut writer = LDWriter::new(&mut cursor, header);
+        writer.log("Speed", "km/h", 2, &[10.0, 20.0, 30.0]).unwrap();
+        let channel = ChannelMetadata {
+            datatype: Datatype::Beacon16,
+            sample_rate: 2,
+            ..sample_channel("Beacon")
+        };
+        let mut handle = writer.begin_channel(channel).unwrap();
+        let raw = [0, 0, 5, 5, 0, 7, 3, 3];
+        handle.write_block(&raw.map(Sample::I16)).unwrap();
+        handle.finish().unwrap();
+
+        let mut reader = LDReader::new(&mut cursor);
+        let beacons = reader.beacons().unwrap();
+        let fired: Vec<_> = beacons.iter().map(|b| (b.time_secs, b.raw)).collect();
+        assert_eq!(fired, [(1.0, 5), (2.5, 7), (3.0, 3)]);
+    }
+
+    #[test]
+    fn beacon_laps() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let header = LDReader::new(&mut Cursor::new(bytes))
+            .read_header()
+            .unwrap();
+
+        // 50 seconds with beacons at 10, 20, 30 and 40 seconds
+        let mut raw = [0i16; 50];
+        for (i, lap) in [(10, 1), (20, 2), (30, 3), (40, 4)] {
+            raw[i] = lap;
+        }
+        let speed: Vec<f64> = (0..100).map(|i| i as f64).collect();
+
+        let mut cursor = Cursor::new(Vec::new());
+        let mut writer = LDWriter::new(&mut cursor, header);
+        writer.log("Speed", "km/h", 2, &speed).unwrap();
+        let channel = ChannelMetadata {
+            datatype: Datatype::Beacon16,
+            sample_rate: 1,
+            ..sample_channel("Beacon")
+        };
+        let mut handle = writer.begin_channel(channel).unwrap();
+        handle.write_block(&raw.map(Sample::I16)).unwrap();
+        handle.finish().unwrap();
+
+        let mut reader = LDReader::new(&mut cursor);
+        let laps = reader.laps().unwrap();
+        let kinds: Vec<_> = laps.iter().map(|l| l.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                LapKind::Out,
+                LapKind::Flying,
+                LapKind::Flying,
+                LapKind::Flying,
+                LapKind::In
+            ]
+        );
+        let bounds: Vec<_> = laps.iter().map(|l| (l.start_secs, l.end_secs)).collect();
+        assert_eq!(
+            bounds,
+            [
+                (0.0, 10.0),
+                (10.0, 20.0),
+                (20.0, 30.0),
+                (30.0, 40.0),
+                (40.0, 50.0)
+            ]
+        );
+        assert_eq!(laps[2].number, 2);
+        assert_eq!(laps[2].duration_secs(), 10.0);
+
+        let channels = reader.read_channels().unwrap();
+        let speed = reader.channel_data(&channels[0]).unwrap();
+        assert_eq!(laps[2].index_range(2), 40..60);
+        let lap = laps[2].slice(&speed, 2);
+        assert_eq!(lap.len(), 20);
+        assert_eq!(lap[0].decode_f64(&channels[0]), 40.0);
+        assert_eq!(laps[4].slice(&speed, 2).len(), 20);
+    }
+
+    #[test]
+    fn sample_count_dropouts() {
         let bytes = fs::read("./samples/Sample1.ld").unwrap();
         let mut cursor = Cursor::new(bytes);
         let mut reader = LDReader::new(&mut cursor);
+        assert_eq!(reader.check_sample_counts(0.0).unwrap(), []);
 
-        let vehicle = reader.read_vehicle().unwrap();
+        let header = reader.read_header().unwrap();
+        let mut cursor = Cursor::new(Vec::new());
+        let mut writer = LDWriter::new(&mut cursor, header);
+        writer.log("Speed", "km/h", 10, &[1.0; 100]).unwrap();
+        writer.log("Lap", "", 1, &[1.0; 10]).unwrap();
+        // 4 seconds short of the other channels
+        writer.log("RPM", "rpm", 10, &[3000.0; 60]).unwrap();
+        // Off by a single sample
+        writer.log("Gear", "", 5, &[1.0; 49]).unwrap();
+
+        let mut reader = LDReader::new(&mut cursor);
+        let channels = reader.read_channels().unwrap();
+        assert_eq!(channels[2].expected_samples(10.0), 100);
 
         assert_eq!(
-            vehicle,
-            Some(Vehicle {
-                id: "11A".to_string(),
-                weight: 0,
-                _type: "Car".to_string(),
-                comment: "".to_string(),
-            })
+            reader.check_sample_counts(0.5).unwrap(),
+            [SampleCountMismatch {
+                channel: "RPM".to_string(),
+                expected: 100,
+                actual: 60,
+                missing_secs: 4.0,
+            }]
+        );
+        let mismatches = reader.check_sample_counts(0.0).unwrap();
+        assert_eq!(mismatches.len(), 2);
+        assert_eq!(mismatches[1].channel, "Gear");
+        assert_eq!(mismatches[1].missing_secs, 0.2);
+    }
+
+    #[test]
+    fn laps_without_beacons() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let header = LDReader::new(&mut Cursor::new(bytes))
+            .read_header()
+            .unwrap();
+
+        let mut cursor = Cursor::new(Vec::new());
+        let mut writer = LDWriter::new(&mut cursor, header);
+        writer.log("Speed", "km/h", 2, &[10.0, 20.0, 30.0]).unwrap();
+
+        let laps = LDReader::new(&mut cursor).laps().unwrap();
+        assert_eq!(
+            laps,
+            [Lap {
+                number: 0,
+                kind: LapKind::Out,
+                start_secs: 0.0,
+                end_secs: 1.5
+            }]
+        );
+        assert_eq!(laps[0].slice(&[1, 2, 3, 4], 2), [1, 2, 3]);
+    }
+
+    #[test]
+    fn sample1_report() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+
+        let report = reader.report().unwrap();
+        assert_eq!(report.header.venue, "Calder");
+        assert_eq!(report.channel_count, 78);
+        assert_delta!(report.duration_secs, 454.0, 0.000001);
+        assert_eq!(
+            report.datatypes,
+            [(Datatype::I16, 77), (Datatype::Beacon16, 1)]
         );
+        assert_eq!(report.rates.values().sum::<usize>(), 78);
+        assert!(report.invalid_channels.is_empty());
+        assert_eq!(report.checksum, ChecksumStatus::NoChecksum);
+    }
+
+    #[test]
+    fn sample1_channel_by_name() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+
+        let channel = reader.channel_by_name("Air Temp Inlet").unwrap().unwrap();
+        assert_eq!(channel.data_addr, 23056);
+        assert_eq!(reader.channel_by_name("air temp inlet").unwrap(), None);
+        assert_eq!(reader.channel_by_name("Tyre Pres FL").unwrap(), None);
+
+        let channel = reader
+            .channel_by_name_ci("AIR TEMP inlet")
+            .unwrap()
+            .unwrap();
+        assert_eq!(channel.name, "Air Temp Inlet");
+        assert_eq!(reader.channel_by_name_ci("Tyre Pres FL").unwrap(), None);
+    }
+
+    #[test]
+    fn sample1_channel_data_iter() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+
+        let channels = reader.read_channels().unwrap();
+        let channel = &channels[77];
+        let expected = reader.channel_data(channel).unwrap();
+
+        let data: Vec<Sample> = reader
+            .channel_data_iter(channel)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(data.len(), 9080);
+        assert_eq!(data, expected);
+
+        // Stops at the first error on a truncated file
+        let mut cursor = Cursor::new(Vec::new());
+        LDWriter::new(&mut cursor, sample_header())
+            .with_channel(sample_channel("Air Temp Inlet"), vec![Sample::I16(1); 4])
+            .write()
+            .unwrap();
+        let mut bytes = cursor.into_inner();
+        bytes.truncate(bytes.len() - 3);
+
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+        let channels = reader.read_channels().unwrap();
+        let results: Vec<_> = reader.channel_data_iter(&channels[0]).unwrap().collect();
+        assert_eq!(results.len(), 3);
+        assert!(results[2].is_err());
     }
 }
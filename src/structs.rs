@@ -1,6 +1,15 @@
-use crate::{I2Error, I2Result};
+use crate::analysis::unit_conversion;
+use crate::{Datatype, I2Error, I2Result, LDReader};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::convert::Infallible;
+use std::fmt;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::ops::Range;
+use std::str::FromStr;
 
 #[derive(Debug, Clone, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Header {
     pub channel_meta_ptr: u32,
     pub channel_data_ptr: u32,
@@ -9,6 +18,8 @@ pub struct Header {
     pub device_serial: u32,
     pub device_type: String,
     pub device_version: u16,
+    /// Unknown, 0x0080 in Sample1.ld. This might be a device or firmware flag.
+    pub device_flags: u16,
 
     pub num_channels: u32,
 
@@ -24,94 +35,559 @@ pub struct Header {
     pub short_comment: String,
 }
 
+impl Header {
+    /// Starts building a header, see [HeaderBuilder]
+    pub fn builder() -> HeaderBuilder {
+        HeaderBuilder::default()
+    }
+
+    /// Parses `key=value` pairs separated by `;` out of the short comment
+    ///
+    /// Keys and values are trimmed, and entries without a `=` are ignored.
+    pub fn parse_short_comment(&self) -> HashMap<String, String> {
+        self.short_comment
+            .split(';')
+            .filter_map(|entry| entry.split_once('='))
+            .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+            .collect()
+    }
+
+    /// Combines [Header::date_string] (DD/MM/YYYY) and [Header::time_string] (HH:MM:SS)
+    ///
+    /// Returns `None` if either of them is empty or malformed.
+    #[cfg(feature = "chrono")]
+    pub fn datetime(&self) -> Option<chrono::NaiveDateTime> {
+        let date = chrono::NaiveDate::parse_from_str(self.date_string.trim(), "%d/%m/%Y").ok()?;
+        let time = chrono::NaiveTime::parse_from_str(self.time_string.trim(), "%H:%M:%S").ok()?;
+        Some(date.and_time(time))
+    }
+
+    /// Classifies the logging device from [Header::device_type]
+    pub fn device_family(&self) -> DeviceFamily {
+        DeviceFamily::from_device_type(&self.device_type)
+    }
+
+    /// Parses [Header::device_type] into a [DeviceType]
+    pub fn device_type_enum(&self) -> DeviceType {
+        let Ok(device_type) = self.device_type.parse();
+        device_type
+    }
+}
+
+/// A one line summary of the device, session and number of channels, for example
+/// `ADL #12007 @ Calder, 23/11/2005 09:53 — 78 channels`
+///
+/// The driver is added in parentheses after the time when it is set.
+impl fmt::Display for Header {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Drop the seconds from HH:MM:SS
+        let time = match self.time_string.rsplit_once(':') {
+            Some((hours_minutes, _)) if hours_minutes.contains(':') => hours_minutes,
+            _ => &self.time_string,
+        };
+
+        write!(
+            f,
+            "{} #{} @ {}, {} {}",
+            self.device_type, self.device_serial, self.venue, self.date_string, time
+        )?;
+        if !self.driver.is_empty() {
+            write!(f, " ({})", self.driver)?;
+        }
+        write!(f, " — {} channels", self.num_channels)
+    }
+}
+
+/// Builds a [Header], with every field that isn't set left empty or 0
+///
+/// The pointers and [Header::num_channels] are left as 0, since [crate::LDWriter] fills them
+/// in when writing the file.
+#[derive(Debug, Clone)]
+pub struct HeaderBuilder {
+    header: Header,
+}
+
+impl Default for HeaderBuilder {
+    fn default() -> Self {
+        Self {
+            header: Header {
+                channel_meta_ptr: 0,
+                channel_data_ptr: 0,
+                event_ptr: 0,
+                device_serial: 0,
+                device_type: String::new(),
+                device_version: 0,
+                device_flags: 0,
+                num_channels: 0,
+                date_string: String::new(),
+                time_string: String::new(),
+                driver: String::new(),
+                vehicleid: String::new(),
+                venue: String::new(),
+                session: String::new(),
+                short_comment: String::new(),
+            },
+        }
+    }
+}
+
+impl HeaderBuilder {
+    pub fn device_serial(mut self, serial: u32) -> Self {
+        self.header.device_serial = serial;
+        self
+    }
+
+    /// Max 8 chars
+    pub fn device_type(mut self, device_type: &str) -> Self {
+        self.header.device_type = device_type.to_string();
+        self
+    }
+
+    pub fn device_version(mut self, version: u16) -> Self {
+        self.header.device_version = version;
+        self
+    }
+
+    pub fn device_flags(mut self, flags: u16) -> Self {
+        self.header.device_flags = flags;
+        self
+    }
+
+    /// Date in the DD/MM/YYYY format
+    pub fn date(mut self, date: &str) -> Self {
+        self.header.date_string = date.to_string();
+        self
+    }
+
+    /// Time in the HH:MM:SS format
+    pub fn time(mut self, time: &str) -> Self {
+        self.header.time_string = time.to_string();
+        self
+    }
+
+    /// Max 64 chars
+    pub fn driver(mut self, driver: &str) -> Self {
+        self.header.driver = driver.to_string();
+        self
+    }
+
+    /// Max 64 chars
+    pub fn vehicleid(mut self, vehicleid: &str) -> Self {
+        self.header.vehicleid = vehicleid.to_string();
+        self
+    }
+
+    /// Max 64 chars
+    pub fn venue(mut self, venue: &str) -> Self {
+        self.header.venue = venue.to_string();
+        self
+    }
+
+    /// Max 64 chars
+    pub fn session(mut self, session: &str) -> Self {
+        self.header.session = session.to_string();
+        self
+    }
+
+    /// Max 64 chars
+    pub fn short_comment(mut self, short_comment: &str) -> Self {
+        self.header.short_comment = short_comment.to_string();
+        self
+    }
+
+    pub fn build(self) -> Header {
+        self.header
+    }
+}
+
+/// Which kind of tool produced a ld file, see [crate::LDReader::format_variant]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FormatVariant {
+    /// Logged by a MoTeC device, the constant header fields match Sample1.ld
+    Standard,
+    /// Produced by a converter from another format (e.g. AiM logs), some of the header fields
+    /// that are constant in files from MoTeC devices have different values
+    Converted,
+}
+
+/// Family of the MoTeC device that produced a log
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DeviceFamily {
+    /// Advanced Dash Logger (ADL, ADL2, ADL3)
+    Adl,
+    /// Sport Dash Logger (SDL, SDL3)
+    Sdl,
+    /// Advanced Central Logger
+    Acl,
+    /// Colour display loggers (C125, C127, C185, C187, C1212)
+    CSeries,
+    /// A device type we don't know about
+    Unknown(String),
+}
+
+impl DeviceFamily {
+    pub fn from_device_type(device_type: &str) -> Self {
+        let device_type = device_type.trim();
+        let is_c_series = device_type.starts_with('C')
+            && device_type.len() > 1
+            && device_type[1..].chars().all(|c| c.is_ascii_digit());
+
+        if device_type.starts_with("ADL") {
+            DeviceFamily::Adl
+        } else if device_type.starts_with("SDL") {
+            DeviceFamily::Sdl
+        } else if device_type.starts_with("ACL") {
+            DeviceFamily::Acl
+        } else if is_c_series {
+            DeviceFamily::CSeries
+        } else {
+            DeviceFamily::Unknown(device_type.to_string())
+        }
+    }
+}
+
+/// Model of the MoTeC device that produced a log, parsed from [Header::device_type]
+///
+/// See [DeviceFamily] for a coarser grouping of these.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DeviceType {
+    Adl,
+    Adl2,
+    Adl3,
+    Sdl,
+    Sdl3,
+    Acl,
+    C125,
+    C127,
+    C185,
+    C187,
+    C1212,
+    /// A device type we don't know about, with surrounding whitespace removed
+    Other(String),
+}
+
+impl DeviceType {
+    /// The device type as stored in [Header::device_type]
+    pub fn as_str(&self) -> &str {
+        match self {
+            DeviceType::Adl => "ADL",
+            DeviceType::Adl2 => "ADL2",
+            DeviceType::Adl3 => "ADL3",
+            DeviceType::Sdl => "SDL",
+            DeviceType::Sdl3 => "SDL3",
+            DeviceType::Acl => "ACL",
+            DeviceType::C125 => "C125",
+            DeviceType::C127 => "C127",
+            DeviceType::C185 => "C185",
+            DeviceType::C187 => "C187",
+            DeviceType::C1212 => "C1212",
+            DeviceType::Other(device_type) => device_type,
+        }
+    }
+
+    pub fn family(&self) -> DeviceFamily {
+        DeviceFamily::from_device_type(self.as_str())
+    }
+}
+
+/// Parsing never fails, unknown device types end up in [DeviceType::Other]
+impl FromStr for DeviceType {
+    type Err = Infallible;
+
+    fn from_str(device_type: &str) -> Result<Self, Self::Err> {
+        Ok(match device_type.trim() {
+            "ADL" => DeviceType::Adl,
+            "ADL2" => DeviceType::Adl2,
+            "ADL3" => DeviceType::Adl3,
+            "SDL" => DeviceType::Sdl,
+            "SDL3" => DeviceType::Sdl3,
+            "ACL" => DeviceType::Acl,
+            "C125" => DeviceType::C125,
+            "C127" => DeviceType::C127,
+            "C185" => DeviceType::C185,
+            "C187" => DeviceType::C187,
+            "C1212" => DeviceType::C1212,
+            other => DeviceType::Other(other.to_string()),
+        })
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Sample {
     I16(i16),
     I32(i32),
+    /// A half precision float, widened to a f32
+    F16(f32),
     F32(f32),
 }
 
 impl Sample {
     /// Calculates the final value of this sample as a f64
+    ///
+    /// The value is `(raw / scale * 10^-dec_places + offset) * mul`, so the offset is applied
+    /// before `mul`. This is the order used by [ldparser](https://github.com/gotzl/ldparser).
+    /// Sample1.ld only has channels with an offset of 0 and a mul of 1, so we haven't been able
+    /// to check it against values exported from i2.
     pub fn decode_f64(&self, channel: &ChannelMetadata) -> f64 {
         let value = match self {
             Sample::I16(v) => *v as f64,
             Sample::I32(v) => *v as f64,
-            Sample::F32(v) => *v as f64,
+            Sample::F16(v) | Sample::F32(v) => *v as f64,
         };
 
-        // TODO: Offset not yet supported
-        assert_eq!(channel.offset, 0);
         let value = value / channel.scale as f64;
         let value = value * (10.0f64.powi(-channel.dec_places as i32));
-        let value = value * channel.mul as f64;
-        value
+        (value + channel.offset as f64) * channel.mul as f64
+    }
+
+    /// Calculates the final value of this sample converted to `unit`
+    ///
+    /// Returns `None` if we don't know how to convert from the channel's unit, see
+    /// [crate::convert_unit].
+    pub fn decode_f64_as(&self, channel: &ChannelMetadata, unit: &str) -> Option<f64> {
+        let (scale, offset) = unit_conversion(&channel.unit, unit)?;
+        Some(self.decode_f64(channel) * scale + offset)
+    }
+
+    /// Calculates the final value of this sample rounded to the channel's `dec_places`
+    ///
+    /// This matches what i2 displays, without the floating point noise of [Sample::decode_f64].
+    pub fn decode_rounded(&self, channel: &ChannelMetadata) -> f64 {
+        let factor = 10.0f64.powi(channel.dec_places as i32);
+        (self.decode_f64(channel) * factor).round() / factor
+    }
+
+    /// Encodes a physical value into a sample for `channel`, this is the inverse of
+    /// [Sample::decode_f64]
+    ///
+    /// Integer samples are rounded to the nearest value, and values that don't fit in the
+    /// channel's datatype are saturated. Use [Sample::encode_f64_checked] to detect those.
+    ///
+    /// Fails with [I2Error::UnsupportedDatatype] for [Datatype::Invalid] channels.
+    pub fn encode_f64(value: f64, channel: &ChannelMetadata) -> I2Result<Sample> {
+        Self::encode_f64_checked(value, channel, OverflowPolicy::Saturate)
+    }
+
+    /// Encodes a physical value into a sample for `channel`, this is the inverse of
+    /// [Sample::decode_f64]
+    ///
+    /// Integer samples are rounded to the nearest value, `policy` decides what happens to values
+    /// that don't fit in the channel's datatype.
+    pub fn encode_f64_checked(
+        value: f64,
+        channel: &ChannelMetadata,
+        policy: OverflowPolicy,
+    ) -> I2Result<Sample> {
+        let raw = value / channel.mul as f64 - channel.offset as f64;
+        let raw = raw * (10.0f64.powi(channel.dec_places as i32));
+        let raw = raw * channel.scale as f64;
+
+        let overflow = || I2Error::SampleOverflow {
+            value,
+            datatype: channel.datatype.clone(),
+        };
+
+        match channel.datatype {
+            Datatype::Beacon16 | Datatype::I16 => {
+                fit_integer(raw, i16::MIN as i64, i16::MAX as i64, policy)
+                    .map(|v| Sample::I16(v as i16))
+                    .ok_or_else(overflow)
+            }
+            Datatype::Beacon32 | Datatype::I32 => {
+                fit_integer(raw, i32::MIN as i64, i32::MAX as i64, policy)
+                    .map(|v| Sample::I32(v as i32))
+                    .ok_or_else(overflow)
+            }
+            Datatype::F16 => {
+                let raw = if raw.is_finite() && raw.abs() > F16_MAX {
+                    match policy {
+                        OverflowPolicy::Error => return Err(overflow()),
+                        OverflowPolicy::Saturate => raw.clamp(-F16_MAX, F16_MAX),
+                        // Stored as an infinity
+                        OverflowPolicy::Wrap => raw,
+                    }
+                } else {
+                    raw
+                };
+                Ok(Sample::F16(raw as f32))
+            }
+            Datatype::F32 => Ok(Sample::F32(raw as f32)),
+            Datatype::Invalid => Err(I2Error::UnsupportedDatatype {
+                datatype: channel.datatype.clone(),
+            }),
+        }
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Hash)]
-pub enum Datatype {
-    // TODO: Not Too sure about this data type, it shows up as beacon in the sample dataset
-    // It behaves as an integer of the same size
-    Beacon16,
-    Beacon32,
+/// Samples are only ordered against samples of the same variant, since that's also how
+/// [PartialEq] is derived. [Sample::F32] samples with a NaN value are unordered.
+impl PartialOrd for Sample {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Sample::I16(a), Sample::I16(b)) => a.partial_cmp(b),
+            (Sample::I32(a), Sample::I32(b)) => a.partial_cmp(b),
+            (Sample::F16(a), Sample::F16(b)) => a.partial_cmp(b),
+            (Sample::F32(a), Sample::F32(b)) => a.partial_cmp(b),
+            _ => None,
+        }
+    }
+}
 
-    I16,
-    I32,
+/// Largest finite value of a half precision float
+const F16_MAX: f64 = 65504.0;
 
-    F16,
-    F32,
+/// Converts the bits of a IEEE 754 half precision float into a f32
+///
+/// Every half is exactly representable as a f32, including subnormals, infinities and NaN.
+pub(crate) fn f16_to_f32(bits: u16) -> f32 {
+    let sign = ((bits >> 15) as u32) << 31;
+    let exponent = ((bits >> 10) & 0x1F) as u32;
+    let mantissa = (bits & 0x3FF) as u32;
 
-    Invalid,
+    let bits = match (exponent, mantissa) {
+        // Signed zero
+        (0, 0) => sign,
+        // Subnormal, these are normal numbers when stored as f32
+        (0, _) => {
+            let shift = mantissa.leading_zeros() - 21;
+            let mantissa = (mantissa << shift) & 0x3FF;
+            let exponent = 127 - 15 + 1 - shift;
+            sign | (exponent << 23) | (mantissa << 13)
+        }
+        // Infinity and NaN
+        (0x1F, _) => sign | (0xFF << 23) | (mantissa << 13),
+        _ => sign | ((exponent + 127 - 15) << 23) | (mantissa << 13),
+    };
+    f32::from_bits(bits)
 }
 
-impl Datatype {
-    /// Size in bytes that this datatype occupies on file
-    pub fn size(&self) -> u16 {
-        match self {
-            Datatype::Beacon16 | Datatype::I16 | Datatype::F16 => 2,
-            Datatype::Beacon32 | Datatype::I32 | Datatype::F32 => 4,
+/// Converts a f32 into the bits of the nearest IEEE 754 half precision float
+///
+/// Ties are rounded to even. Values too large for a half become infinities, and values too
+/// small become (signed) zeros.
+pub(crate) fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xFF) as i32;
+    let mantissa = bits & 0x7F_FFFF;
 
-            // We really don't know what these values are
-            Datatype::Invalid => 0,
+    // Infinity and NaN, keeping NaNs quiet
+    if exponent == 0xFF {
+        let nan = if mantissa != 0 {
+            0x200 | (mantissa >> 13) as u16
+        } else {
+            0
+        };
+        return sign | 0x7C00 | nan;
+    }
+
+    // Drops the lowest `shift` bits of `value`, rounding to nearest even
+    let round = |value: u32, shift: u32| {
+        let truncated = value >> shift;
+        let rest = value & ((1 << shift) - 1);
+        let halfway = 1 << (shift - 1);
+        if rest > halfway || (rest == halfway && truncated & 1 == 1) {
+            truncated + 1
+        } else {
+            truncated
         }
+    };
+
+    let exponent = exponent - 127 + 15;
+    if exponent >= 0x1F {
+        sign | 0x7C00
+    } else if exponent <= 0 {
+        // Subnormal, including the implicit leading bit in the mantissa
+        if exponent < -10 {
+            return sign;
+        }
+        sign | round(mantissa | 0x80_0000, (14 - exponent) as u32) as u16
+    } else {
+        // A carry out of the mantissa correctly bumps the exponent, up to infinity
+        sign | round(((exponent as u32) << 23) | mantissa, 13) as u16
     }
+}
 
-    pub fn _type(&self) -> u16 {
-        match self {
-            Datatype::Beacon16 | Datatype::Beacon32 => 0,
-            Datatype::I16 | Datatype::I32 => 3,
-            Datatype::F16 | Datatype::F32 => 7,
-            Datatype::Invalid => 999,
+/// Rounds `raw` into an integer in the `min..=max` range according to `policy`
+///
+/// For [OverflowPolicy::Wrap] the value is returned unchecked, and is expected to be
+/// truncated by the caller.
+fn fit_integer(raw: f64, min: i64, max: i64, policy: OverflowPolicy) -> Option<i64> {
+    let rounded = raw.round();
+    match policy {
+        OverflowPolicy::Error => {
+            if rounded >= min as f64 && rounded <= max as f64 {
+                Some(rounded as i64)
+            } else {
+                None
+            }
         }
+        OverflowPolicy::Saturate => Some((rounded as i64).clamp(min, max)),
+        OverflowPolicy::Wrap => Some(rounded as i64),
     }
+}
 
-    pub fn from_type_and_size(_type: u16, size: u16) -> I2Result<Self> {
-        match (_type, size) {
-            (0, 2) => Ok(Datatype::Beacon16),
-            (0, 4) => Ok(Datatype::Beacon32),
-            (3, 2) => Ok(Datatype::I16),
-            (3, 4) => Ok(Datatype::I32),
-            // 20160903-0051401.ld uses 5 for ints?
-            (5, 2) => Ok(Datatype::I16),
-            (5, 4) => Ok(Datatype::I32),
-            (7, 2) => Ok(Datatype::F16),
-            (7, 4) => Ok(Datatype::F32),
+/// What to do when a physical value does not fit in the datatype of a channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum OverflowPolicy {
+    /// Return an [I2Error::SampleOverflow]
+    #[default]
+    Error,
+    /// Clamp the value to the closest value that fits
+    Saturate,
+    /// Truncate the value to the size of the datatype, wrapping around
+    Wrap,
+}
 
-            // The mu iracing exporter exports these values on Damper Pos FL/FR/RL, they have 0 samples
-            (17536, 5) | (6566, 5) | (29813, 5) => Ok(Datatype::Invalid),
-            // This should be Beacon40 ?, but the iRacing mu exporter puts this in Damper Pos RR
-            (0, 5) => Ok(Datatype::Invalid),
-            // Iracing mu exporter Ride Height Center 0 samples
-            (15, 5) => Ok(Datatype::Invalid),
-            _ => Err(I2Error::UnrecognizedDatatype { _type, size }),
-        }
+/// How values between the samples of a channel are computed, see [crate::align_channels]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Interpolation {
+    /// Hold the value of the previous sample
+    #[default]
+    ZeroOrderHold,
+    /// Interpolate linearly between the previous and next samples
+    Linear,
+}
+
+/// An offset from the start of a ld file, as stored in the pointers of the file
+///
+/// A 0 address is used to mean that there is nothing there, since that's where the header is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct FileAddr(pub u32);
+
+impl FileAddr {
+    pub fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Seeks `source` to this address
+    pub fn seek<S: Seek + ?Sized>(self, source: &mut S) -> io::Result<u64> {
+        source.seek(SeekFrom::Start(self.0 as u64))
+    }
+}
+
+impl From<u32> for FileAddr {
+    fn from(addr: u32) -> Self {
+        FileAddr(addr)
+    }
+}
+
+impl From<u16> for FileAddr {
+    fn from(addr: u16) -> Self {
+        FileAddr(addr as u32)
+    }
+}
+
+impl From<FileAddr> for u64 {
+    fn from(addr: FileAddr) -> Self {
+        addr.0 as u64
     }
 }
 
 /// ChannelMetadata is a doubly linked list of blocks in the file
 /// This only contains info about a channel, actual data is stored somewhere else on the file.
 #[derive(Debug, Clone, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ChannelMetadata {
     pub prev_addr: u32,
     pub next_addr: u32,
@@ -123,9 +599,10 @@ pub struct ChannelMetadata {
     /// Sample Rate in Hz
     pub sample_rate: u16,
 
-    pub offset: u16,
-    pub mul: u16,
-    pub scale: u16,
+    /// Signed like the rest of the scaling fields, see [Sample::decode_f64]
+    pub offset: i16,
+    pub mul: i16,
+    pub scale: i16,
     pub dec_places: i16,
 
     pub name: String,
@@ -136,6 +613,66 @@ pub struct ChannelMetadata {
 impl ChannelMetadata {
     /// Size of a metadata entry in bytes
     pub(crate) const ENTRY_SIZE: u32 = 124;
+    /// Size of the fields that we know about at the start of a metadata entry
+    pub(crate) const FIXED_SIZE: u32 = 84;
+    /// Size of the unknown trailer at the end of a metadata entry
+    pub(crate) const TRAILER_SIZE: usize = (Self::ENTRY_SIZE - Self::FIXED_SIZE) as usize;
+    /// Largest trailer that we accept when inferring the entry size from the linked list
+    pub(crate) const MAX_TRAILER_SIZE: u32 = 256;
+
+    /// Sets `dec_places` and `scale` so that the stored samples keep `decimals` decimal places
+    ///
+    /// The raw samples are the physical value multiplied by `10^decimals`, so make sure that
+    /// the datatype is large enough for the range of values being stored.
+    pub fn with_precision(mut self, decimals: u8) -> Self {
+        self.dec_places = decimals as i16;
+        self.scale = 1;
+        self
+    }
+
+    /// How many seconds the channel's samples span, 0 for channels with a 0 sample rate
+    pub fn duration_secs(&self) -> f64 {
+        if self.sample_rate == 0 {
+            return 0.0;
+        }
+        self.data_count as f64 / self.sample_rate as f64
+    }
+
+    /// How many samples the channel should have to cover `duration_secs` at its sample rate
+    pub fn expected_samples(&self, duration_secs: f64) -> u32 {
+        (self.sample_rate as f64 * duration_secs).round() as u32
+    }
+
+    /// Time in seconds of the sample at `index`, 0 for channels with a 0 sample rate like in
+    /// [TimeBase::times]
+    pub fn sample_time(&self, index: usize) -> f64 {
+        if self.sample_rate == 0 {
+            return 0.0;
+        }
+        index as f64 / self.sample_rate as f64
+    }
+
+    /// Time in seconds of each sample, at `index / sample_rate`
+    ///
+    /// Channels with a 0 sample rate have an empty time axis.
+    pub fn time_axis(&self) -> impl Iterator<Item = f64> {
+        let rate = self.sample_rate as f64;
+        let count = if self.sample_rate == 0 {
+            0
+        } else {
+            self.data_count
+        };
+        (0..count).map(move |i| i as f64 / rate)
+    }
+
+    /// Returns the scale and offset that convert decoded values from unit `from` to unit `to`,
+    /// as in `value * scale + offset`
+    ///
+    /// `from` is usually the channel's own `unit`. Returns `None` if either unit is unknown or
+    /// they measure different things, see [crate::convert_unit] for the known units.
+    pub fn convert_unit(&self, from: &str, to: &str) -> Option<(f64, f64)> {
+        unit_conversion(from, to)
+    }
 
     /// Calculates the size in bytes of the data section for this channel
     pub(crate) fn data_size(&self) -> u32 {
@@ -143,7 +680,323 @@ impl ChannelMetadata {
     }
 }
 
+/// All the metadata in a ld file, without any of the sample data
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LDFile {
+    pub header: Header,
+    pub event: Option<Event>,
+    pub venue: Option<Venue>,
+    pub vehicle: Option<Vehicle>,
+    pub channels: Vec<ChannelMetadata>,
+}
+
+impl LDFile {
+    /// Decodes every channel into a [TimeSeriesSet] using the samples read from `reader`
+    ///
+    /// `reader` must be reading the same file this was read from. Channels with a datatype that
+    /// we can't decode are skipped.
+    pub fn into_time_series<S: Read + Seek>(
+        self,
+        reader: &mut LDReader<S>,
+    ) -> I2Result<TimeSeriesSet> {
+        let series = self
+            .channels
+            .iter()
+            .filter(|c| c.datatype != Datatype::Invalid)
+            .map(|c| reader.decode_channel(c))
+            .collect::<I2Result<_>>()?;
+        Ok(TimeSeriesSet { series })
+    }
+}
+
+/// The decoded channels of a file, each with its own time axis
+///
+/// This doesn't depend on any particular data analysis library, see
+/// [LDFile::into_time_series].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TimeSeriesSet {
+    pub series: Vec<DecodedChannel>,
+}
+
+/// Source of the timestamps of the samples in a file, see [crate::LDReader::time_base]
+#[derive(Debug, Clone, PartialEq)]
+pub enum TimeBase {
+    /// Samples are evenly spaced, at `index / sample_rate` seconds
+    SampleRate,
+    /// Timestamps in seconds come from a channel in the file
+    Channel {
+        channel: ChannelMetadata,
+        times: Vec<f64>,
+    },
+}
+
+impl TimeBase {
+    /// Channel names that are recognized as an explicit time channel
+    pub const TIME_CHANNEL_NAMES: &'static [&'static str] =
+        &["Time", "Session Time", "Elapsed Time"];
+
+    /// Calculates the timestamps in seconds of the samples of `channel`
+    ///
+    /// With a time channel, each sample uses the timestamp of the time channel sample that was
+    /// logged at the same point, by scaling the index with the ratio of the sample rates.
+    pub fn times(&self, channel: &ChannelMetadata) -> Vec<f64> {
+        let rate = channel.sample_rate as f64;
+        match self {
+            _ if channel.sample_rate == 0 => vec![0.0; channel.data_count as usize],
+            TimeBase::SampleRate => (0..channel.data_count).map(|i| i as f64 / rate).collect(),
+            TimeBase::Channel {
+                channel: time_channel,
+                times,
+            } => {
+                let ratio = time_channel.sample_rate as f64 / rate;
+                (0..channel.data_count)
+                    .map(|i| {
+                        let index = (i as f64 * ratio) as usize;
+                        times
+                            .get(index.min(times.len().saturating_sub(1)))
+                            .copied()
+                            .unwrap_or(i as f64 / rate)
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// A channel with all of its samples decoded into physical values
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DecodedChannel {
+    pub name: String,
+    pub unit: String,
+    /// Sample Rate in Hz
+    pub sample_rate: u16,
+
+    /// Decoded values, see [Sample::decode_f64]
+    pub values: Vec<f64>,
+    /// Time in seconds of each value, relative to the start of the log
+    pub times: Vec<f64>,
+}
+
+impl DecodedChannel {
+    /// Time in seconds spanned by this channel's samples
+    pub fn duration_secs(&self) -> f64 {
+        if self.sample_rate == 0 {
+            return 0.0;
+        }
+        self.values.len() as f64 / self.sample_rate as f64
+    }
+}
+
+/// The driver input channels of a log, resampled onto a common time base
+///
+/// Channels that could not be found in the file are `None`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DriverInputs {
+    /// Time in seconds of each value, shared by all inputs
+    pub times: Vec<f64>,
+
+    pub throttle: Option<Vec<f64>>,
+    pub brake: Option<Vec<f64>>,
+    pub steering: Option<Vec<f64>>,
+}
+
+impl DriverInputs {
+    /// Channel names that are recognized as the throttle position
+    pub const THROTTLE_NAMES: &'static [&'static str] = &["Throttle Pos", "Throttle Position"];
+    /// Channel names that are recognized as the brake pressure
+    pub const BRAKE_NAMES: &'static [&'static str] =
+        &["Brake Pres Front", "Brake Pres", "Brake Pressure"];
+    /// Channel names that are recognized as the steering angle
+    pub const STEERING_NAMES: &'static [&'static str] = &["Steered Angle", "Steering Angle"];
+}
+
+/// Result of [crate::LDReader::verify_checksum]
+///
+/// As far as we know the ld format does not contain a checksum, files end right after the last
+/// channel's data section.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ChecksumStatus {
+    /// The file ends after the last data section, there is no checksum to verify
+    NoChecksum,
+    /// There are bytes after the last data section that we don't know how to interpret
+    UnknownTrailer { len: u64 },
+}
+
+/// Distinct session metadata across many files, see [crate::aggregate_metadata]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct MetadataIndex {
+    pub drivers: BTreeSet<String>,
+    pub venues: BTreeSet<String>,
+    pub vehicles: BTreeSet<String>,
+}
+
+/// A channel's data section that was cut short by the end of the file, see
+/// [crate::LDReader::with_partial_reads]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PartiallyRead {
+    /// Name of the channel that was read
+    pub channel: String,
+    /// Number of samples that the channel should have
+    pub expected: u32,
+    /// Number of complete samples that were read
+    pub read: u32,
+}
+
+/// Where the contents of a file ended up, see [crate::LDWriter::write_with_layout]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct WrittenLayout {
+    /// Channels in the order they were written
+    pub channels: Vec<ChannelLayout>,
+}
+
+/// Location of a channel in a written file
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ChannelLayout {
+    pub name: String,
+    /// Offset of the channel's metadata entry
+    pub meta_addr: u32,
+    /// Offset of the channel's data section
+    pub data_addr: u32,
+    /// Size in bytes of the channel's data section
+    pub data_size: u32,
+}
+
+/// Summary of a whole file, see [crate::LDReader::report]
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileReport {
+    pub header: Header,
+    pub channel_count: usize,
+    /// Number of channels of each datatype, in the order they were first seen
+    pub datatypes: Vec<(Datatype, usize)>,
+    /// Number of channels at each sample rate
+    pub rates: BTreeMap<u16, usize>,
+    /// Total number of samples across all channels
+    pub total_samples: u64,
+    /// Duration in seconds of the longest channel
+    pub duration_secs: f64,
+
+    /// Names of the channels with a [Datatype::Invalid]
+    pub invalid_channels: Vec<String>,
+    /// Names of the channels without any samples
+    pub empty_channels: Vec<String>,
+    pub checksum: ChecksumStatus,
+}
+
+/// A lap beacon firing in a [Datatype::Beacon16] or [Datatype::Beacon32] channel, see
+/// [crate::LDReader::beacons]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Beacon {
+    /// Time in seconds of the sample where the beacon fired
+    pub time_secs: f64,
+    /// Raw value of that sample
+    pub raw: i32,
+}
+
+/// Which part of a session a [Lap] is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LapKind {
+    /// From the start of the log up to the first beacon
+    Out,
+    /// Between two beacons
+    Flying,
+    /// From the last beacon to the end of the log
+    In,
+}
+
+/// A lap of a session, see [crate::LDReader::laps]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lap {
+    /// Position of the lap in the session, starting at 0 for the out lap
+    pub number: usize,
+    pub kind: LapKind,
+    /// Time in seconds where the lap starts
+    pub start_secs: f64,
+    /// Time in seconds where the lap ends, and the next one starts
+    pub end_secs: f64,
+}
+
+impl Lap {
+    pub fn duration_secs(&self) -> f64 {
+        self.end_secs - self.start_secs
+    }
+
+    /// Range of the samples of a channel logged at `sample_rate` that fall in this lap
+    pub fn index_range(&self, sample_rate: u16) -> Range<usize> {
+        let index = |secs: f64| (secs * sample_rate as f64).round() as usize;
+        index(self.start_secs)..index(self.end_secs)
+    }
+
+    /// Slices the samples of a channel logged at `sample_rate` to this lap
+    ///
+    /// The range is cut short if the channel ends before the lap does.
+    pub fn slice<'a, T>(&self, data: &'a [T], sample_rate: u16) -> &'a [T] {
+        let range = self.index_range(sample_rate);
+        let end = range.end.min(data.len());
+        &data[range.start.min(end)..end]
+    }
+}
+
+/// A channel with a different number of samples than its sample rate needs to cover the log,
+/// see [crate::LDReader::check_sample_counts]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SampleCountMismatch {
+    /// Name of the channel
+    pub channel: String,
+    /// Samples needed to cover the log, see [ChannelMetadata::expected_samples]
+    pub expected: u32,
+    /// Samples in the channel, its [ChannelMetadata::data_count]
+    pub actual: u32,
+    /// Time in seconds covered by the missing samples, negative if the channel has extra ones
+    pub missing_secs: f64,
+}
+
+/// A problem found by [crate::LDReader::validate]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Warning {
+    /// The file doesn't start with a ld header, nothing else was checked
+    InvalidHeaderMarker { found: u32 },
+    /// The file ends before the header does, nothing else was checked
+    TruncatedHeader,
+    /// A header pointer points past the end of the file
+    PointerOutOfBounds {
+        pointer: &'static str,
+        addr: u32,
+        file_size: u64,
+    },
+    /// A channel metadata entry doesn't fit in the file, the list walk stops there
+    MetadataOutOfBounds { addr: u32, file_size: u64 },
+    /// The data section of a channel doesn't fit in the file
+    ChannelDataOutOfBounds {
+        channel: String,
+        data_addr: u32,
+        data_size: u32,
+        file_size: u64,
+    },
+    /// The channel metadata list loops back to an entry that was already visited
+    ChannelListCycle { at: u32 },
+    /// The channel metadata entry at `addr` has a datatype we don't know, so its data section
+    /// wasn't checked
+    UnrecognizedDatatype { addr: u32, _type: u16, size: u16 },
+    /// A name, short name or unit of the channel metadata entry at `addr` isn't valid utf8, so
+    /// its data section wasn't checked
+    NonUtf8String { addr: u32 },
+}
+
+/// A sample outside of the bounds set with [crate::LDReader::with_range_check]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RangeWarning {
+    /// Name of the channel the sample belongs to
+    pub channel: String,
+    /// Index of the sample in the channel data
+    pub index: usize,
+    /// Decoded value of the sample, see [Sample::decode_f64]
+    pub value: f64,
+}
+
 #[derive(Debug, Clone, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Event {
     /// Max 64 chars
     pub name: String,
@@ -155,15 +1008,34 @@ pub struct Event {
     pub venue_addr: u16,
 }
 
+impl Event {
+    /// Offset of [Event::venue_addr] inside the event block
+    pub(crate) const VENUE_ADDR_OFFSET: u32 = 64 + 64 + 1024;
+}
+
 #[derive(Debug, Clone, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Venue {
     /// Max 64 chars
     pub name: String,
 
+    /// The 1034 bytes between the name and `vehicle_addr` that we don't know how to interpret yet
+    ///
+    /// This may contain a track outline, but they are all zeros in Sample1.ld.
+    pub raw_extra: Vec<u8>,
+
     pub vehicle_addr: u16,
 }
 
+impl Venue {
+    /// Size of [Venue::raw_extra] in bytes
+    pub(crate) const RAW_EXTRA_SIZE: usize = 1034;
+    /// Offset of [Venue::vehicle_addr] inside the venue block
+    pub(crate) const VEHICLE_ADDR_OFFSET: u32 = 64 + Self::RAW_EXTRA_SIZE as u32;
+}
+
 #[derive(Debug, Clone, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Vehicle {
     /// Max 64 chars
     pub id: String,
@@ -173,3 +1045,419 @@ pub struct Vehicle {
     /// Max 32 chars
     pub comment: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::structs::{f16_to_f32, f32_to_f16};
+    use crate::{
+        ChannelMetadata, Datatype, DeviceFamily, DeviceType, FileAddr, Header, I2Error, LDReader,
+        OverflowPolicy, Sample,
+    };
+    use std::fs;
+    use std::io::{Cursor, Read};
+
+    fn sample_header() -> Header {
+        Header {
+            channel_meta_ptr: 13384,
+            channel_data_ptr: 23056,
+            event_ptr: 1762,
+            device_serial: 12007,
+            device_type: "ADL".to_string(),
+            device_version: 420,
+            device_flags: 0x0080,
+            num_channels: 1,
+            date_string: "23/11/2005".to_string(),
+            time_string: "09:53:00".to_string(),
+            driver: "".to_string(),
+            vehicleid: "11A".to_string(),
+            venue: "Calder".to_string(),
+            session: "2".to_string(),
+            short_comment: "second warmup".to_string(),
+        }
+    }
+
+    #[test]
+    fn parse_short_comment() {
+        let header = Header {
+            short_comment: "tyre=soft;fuel=30".to_string(),
+            ..sample_header()
+        };
+
+        let notes = header.parse_short_comment();
+        assert_eq!(notes.len(), 2);
+        assert_eq!(notes["tyre"], "soft");
+        assert_eq!(notes["fuel"], "30");
+
+        assert!(sample_header().parse_short_comment().is_empty());
+    }
+
+    #[test]
+    fn header_display() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let header = LDReader::new(&mut Cursor::new(bytes))
+            .read_header()
+            .unwrap();
+        assert_eq!(
+            header.to_string(),
+            "ADL #12007 @ Calder, 23/11/2005 09:53 — 78 channels"
+        );
+
+        let header = Header {
+            driver: "Afonso".to_string(),
+            time_string: "09:53".to_string(),
+            ..header
+        };
+        assert_eq!(
+            header.to_string(),
+            "ADL #12007 @ Calder, 23/11/2005 09:53 (Afonso) — 78 channels"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "chrono")]
+    fn header_datetime() {
+        use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+
+        assert_eq!(
+            sample_header().datetime(),
+            Some(NaiveDateTime::new(
+                NaiveDate::from_ymd_opt(2005, 11, 23).unwrap(),
+                NaiveTime::from_hms_opt(9, 53, 0).unwrap()
+            ))
+        );
+
+        let empty = Header {
+            date_string: "".to_string(),
+            ..sample_header()
+        };
+        assert_eq!(empty.datetime(), None);
+
+        let garbage = Header {
+            date_string: "31/02/20x5".to_string(),
+            ..sample_header()
+        };
+        assert_eq!(garbage.datetime(), None);
+
+        let bad_time = Header {
+            time_string: "25:00:00".to_string(),
+            ..sample_header()
+        };
+        assert_eq!(bad_time.datetime(), None);
+    }
+
+    #[test]
+    fn file_addr() {
+        assert!(FileAddr(0).is_zero());
+        assert!(!FileAddr::from(0x6E2u16).is_zero());
+
+        let mut cursor = Cursor::new(vec![0u8, 1, 2, 3, 4, 5]);
+        assert_eq!(FileAddr(4).seek(&mut cursor).unwrap(), 4);
+        let mut rest = Vec::new();
+        cursor.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, [4, 5]);
+    }
+
+    #[test]
+    fn device_family() {
+        assert_eq!(sample_header().device_family(), DeviceFamily::Adl);
+
+        assert_eq!(DeviceFamily::from_device_type("ADL3"), DeviceFamily::Adl);
+        assert_eq!(
+            DeviceFamily::from_device_type("C125"),
+            DeviceFamily::CSeries
+        );
+        assert_eq!(
+            DeviceFamily::from_device_type("XYZ"),
+            DeviceFamily::Unknown("XYZ".to_string())
+        );
+    }
+
+    #[test]
+    fn device_type() {
+        assert_eq!(sample_header().device_type_enum(), DeviceType::Adl);
+        assert_eq!("ADL".parse(), Ok(DeviceType::Adl));
+        assert_eq!(" C1212 ".parse(), Ok(DeviceType::C1212));
+        assert_eq!(DeviceType::Adl3.as_str(), "ADL3");
+        assert_eq!(DeviceType::C125.family(), DeviceFamily::CSeries);
+
+        let unknown: DeviceType = "M150 ".parse().unwrap();
+        assert_eq!(unknown, DeviceType::Other("M150".to_string()));
+        assert_eq!(unknown.as_str(), "M150");
+        assert_eq!(unknown.family(), DeviceFamily::Unknown("M150".to_string()));
+    }
+
+    #[test]
+    fn channel_with_precision() {
+        let channel = ChannelMetadata {
+            prev_addr: 0,
+            next_addr: 0,
+            data_addr: 0,
+            data_count: 0,
+            datatype: Datatype::I16,
+            sample_rate: 10,
+            offset: 0,
+            mul: 1,
+            scale: 4,
+            dec_places: 0,
+            name: "Oil Pres".to_string(),
+            short_name: "Oil Pre".to_string(),
+            unit: "bar".to_string(),
+        }
+        .with_precision(2);
+        assert_eq!(channel.dec_places, 2);
+        assert_eq!(channel.scale, 1);
+
+        let sample = Sample::encode_f64_checked(12.34, &channel, OverflowPolicy::Error).unwrap();
+        assert_eq!(sample, Sample::I16(1234));
+        assert!((sample.decode_f64(&channel) - 12.34).abs() < 1e-9);
+    }
+
+    #[test]
+    fn encode_i16() {
+        let channel = ChannelMetadata {
+            prev_addr: 0,
+            next_addr: 0,
+            data_addr: 0,
+            data_count: 0,
+            datatype: Datatype::I16,
+            sample_rate: 10,
+            offset: 0,
+            mul: 3,
+            scale: 2,
+            dec_places: 1,
+            name: "Wheel Speed".to_string(),
+            short_name: "WSpd".to_string(),
+            unit: "km/h".to_string(),
+        };
+
+        // raw = 45.3 / 3 * 10 * 2
+        assert_eq!(
+            Sample::encode_f64(45.3, &channel).unwrap(),
+            Sample::I16(302)
+        );
+        assert_eq!(
+            Sample::encode_f64(45.31, &channel).unwrap(),
+            Sample::I16(302)
+        );
+        assert_eq!(
+            Sample::encode_f64(-45.3, &channel).unwrap(),
+            Sample::I16(-302)
+        );
+        assert_eq!(
+            Sample::encode_f64(1e9, &channel).unwrap(),
+            Sample::I16(i16::MAX)
+        );
+
+        let invalid = ChannelMetadata {
+            datatype: Datatype::Invalid,
+            ..channel.clone()
+        };
+        assert!(matches!(
+            Sample::encode_f64(45.3, &invalid),
+            Err(I2Error::UnsupportedDatatype {
+                datatype: Datatype::Invalid
+            })
+        ));
+
+        // The resolution is mul / (scale * 10^dec_places)
+        let resolution = 3.0 / 20.0;
+        for value in [0.0, 1.0, 12.345, 100.7, -250.0, 4000.0] {
+            let decoded = Sample::encode_f64(value, &channel)
+                .unwrap()
+                .decode_f64(&channel);
+            assert!((decoded - value).abs() <= resolution / 2.0);
+        }
+    }
+
+    #[test]
+    fn decode_offset_and_mul() {
+        let channel = ChannelMetadata {
+            prev_addr: 0,
+            next_addr: 0,
+            data_addr: 0,
+            data_count: 0,
+            datatype: Datatype::I32,
+            sample_rate: 10,
+            offset: 1,
+            mul: 2,
+            scale: 2,
+            dec_places: 2,
+            name: "Engine temp".to_string(),
+            short_name: "EngTemp".to_string(),
+            unit: "C".to_string(),
+        };
+
+        // These only check the values against the formula in the docs of Sample::decode_f64,
+        // i.e. (raw / 2 * 10^-2 + 1) * 2. They weren't checked against values exported from i2.
+        for (raw, value) in [(0, 2.0), (10, 2.1), (200, 4.0), (-200, 0.0), (-600, -4.0)] {
+            let sample = Sample::I32(raw);
+            assert!((sample.decode_f64(&channel) - value).abs() < 1e-12);
+            assert_eq!(sample.decode_rounded(&channel), value);
+            assert_eq!(Sample::encode_f64(value, &channel).unwrap(), sample);
+        }
+
+        let float = ChannelMetadata {
+            datatype: Datatype::F32,
+            ..channel
+        };
+        assert_eq!(Sample::F32(150.0).decode_rounded(&float), 3.5);
+        assert_eq!(Sample::encode_f64(3.5, &float).unwrap(), Sample::F32(150.0));
+    }
+
+    #[test]
+    fn decode_negative_offset() {
+        let channel = ChannelMetadata {
+            prev_addr: 0,
+            next_addr: 0,
+            data_addr: 0,
+            data_count: 0,
+            datatype: Datatype::I16,
+            sample_rate: 10,
+            offset: -40,
+            mul: 1,
+            scale: 1,
+            dec_places: 1,
+            name: "Oil Temp".to_string(),
+            short_name: "OilTemp".to_string(),
+            unit: "C".to_string(),
+        };
+
+        // raw / 10 - 40
+        for (raw, value) in [(0, -40.0), (400, 0.0), (1305, 90.5)] {
+            let sample = Sample::I16(raw);
+            assert_eq!(sample.decode_rounded(&channel), value);
+            assert_eq!(Sample::encode_f64(value, &channel).unwrap(), sample);
+        }
+    }
+
+    #[test]
+    fn encode_f32() {
+        let channel = ChannelMetadata {
+            prev_addr: 0,
+            next_addr: 0,
+            data_addr: 0,
+            data_count: 0,
+            datatype: Datatype::F32,
+            sample_rate: 10,
+            offset: 0,
+            mul: 1,
+            scale: 10,
+            dec_places: 2,
+            name: "Lambda".to_string(),
+            short_name: "Lam".to_string(),
+            unit: "".to_string(),
+        };
+
+        assert_eq!(
+            Sample::encode_f64(0.875, &channel).unwrap(),
+            Sample::F32(875.0)
+        );
+        for value in [0.0, 0.875, 1.0123, -3.5, 12345.678] {
+            let decoded = Sample::encode_f64(value, &channel)
+                .unwrap()
+                .decode_f64(&channel);
+            assert!((decoded - value).abs() <= value.abs() * f32::EPSILON as f64);
+        }
+    }
+
+    #[test]
+    fn channel_duration() {
+        let channel = ChannelMetadata {
+            prev_addr: 0,
+            next_addr: 0,
+            data_addr: 0,
+            data_count: 908,
+            datatype: Datatype::I16,
+            sample_rate: 2,
+            offset: 0,
+            mul: 1,
+            scale: 1,
+            dec_places: 1,
+            name: "Air Temp Inlet".to_string(),
+            short_name: "Air Tem".to_string(),
+            unit: "C".to_string(),
+        };
+        assert_eq!(channel.duration_secs(), 454.0);
+
+        let times: Vec<f64> = channel.time_axis().collect();
+        assert_eq!(times.len(), 908);
+        assert_eq!(times[..3], [0.0, 0.5, 1.0]);
+        assert_eq!(times[907], 453.5);
+        assert_eq!(channel.sample_time(907), 453.5);
+
+        let no_rate = ChannelMetadata {
+            sample_rate: 0,
+            ..channel
+        };
+        assert_eq!(no_rate.duration_secs(), 0.0);
+        assert_eq!(no_rate.time_axis().count(), 0);
+        assert_eq!(no_rate.sample_time(907), 0.0);
+    }
+
+    #[test]
+    fn decode_rounded() {
+        let channel = ChannelMetadata {
+            prev_addr: 0,
+            next_addr: 0,
+            data_addr: 0,
+            data_count: 0,
+            datatype: Datatype::I16,
+            sample_rate: 2,
+            offset: 0,
+            mul: 1,
+            scale: 1,
+            dec_places: 1,
+            name: "Air Temp Inlet".to_string(),
+            short_name: "Air Tem".to_string(),
+            unit: "C".to_string(),
+        };
+
+        let sample = Sample::I16(199);
+        assert_ne!(sample.decode_f64(&channel), 19.9);
+        assert_eq!(sample.decode_rounded(&channel), 19.9);
+        assert_eq!(Sample::I16(-1234).decode_rounded(&channel), -123.4);
+    }
+
+    #[test]
+    fn f16_conversion() {
+        assert_eq!(f16_to_f32(0x3C00), 1.0);
+        assert_eq!(f16_to_f32(0xC000), -2.0);
+        assert_eq!(f16_to_f32(0x7BFF), 65504.0);
+        assert_eq!(f16_to_f32(0x0000).to_bits(), 0.0f32.to_bits());
+        assert_eq!(f16_to_f32(0x8000).to_bits(), (-0.0f32).to_bits());
+
+        // Subnormals
+        assert_eq!(f16_to_f32(0x0001), 2.0f32.powi(-24));
+        assert_eq!(f16_to_f32(0x03FF), 1023.0 * 2.0f32.powi(-24));
+        assert_eq!(f16_to_f32(0x8200), -(2.0f32.powi(-15)));
+
+        assert_eq!(f16_to_f32(0x7C00), f32::INFINITY);
+        assert_eq!(f16_to_f32(0xFC00), f32::NEG_INFINITY);
+        assert!(f16_to_f32(0x7E00).is_nan());
+    }
+
+    #[test]
+    fn f16_encoding() {
+        assert_eq!(f32_to_f16(1.0), 0x3C00);
+        assert_eq!(f32_to_f16(-2.0), 0xC000);
+        assert_eq!(f32_to_f16(65504.0), 0x7BFF);
+        assert_eq!(f32_to_f16(-0.0), 0x8000);
+        assert_eq!(f32_to_f16(2.0f32.powi(-24)), 0x0001);
+        assert_eq!(f32_to_f16(2.0f32.powi(-26)), 0x0000);
+        assert_eq!(f32_to_f16(65520.0), 0x7C00);
+        assert_eq!(f32_to_f16(f32::NEG_INFINITY), 0xFC00);
+        assert!(f16_to_f32(f32_to_f16(f32::NAN)).is_nan());
+
+        // Ties round to even
+        assert_eq!(f32_to_f16(1.0 + 2.0f32.powi(-11)), 0x3C00);
+        assert_eq!(f32_to_f16(1.0 + 3.0 * 2.0f32.powi(-11)), 0x3C02);
+
+        // Every half survives a round trip
+        for bits in 0..=u16::MAX {
+            let value = f16_to_f32(bits);
+            if !value.is_nan() {
+                assert_eq!(f32_to_f16(value), bits);
+            }
+        }
+    }
+}
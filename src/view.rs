@@ -0,0 +1,448 @@
+use crate::{Datatype, I2Error, I2Result};
+use alloc::borrow::Cow;
+use alloc::string::String;
+use core::str;
+
+/// Marker at the start of every ld file
+pub(crate) const LD_HEADER_MARKER: u32 = 64;
+
+/// A zero allocation view of a ld file held in memory
+///
+/// Unlike [crate::LDReader], accessors return slices borrowed from the underlying buffer, so
+/// scanning many files doesn't allocate. Only the fields that we know about are exposed, see
+/// [crate::Header] and [crate::ChannelMetadata] for their meaning.
+#[derive(Debug, Clone, Copy)]
+pub struct LDView<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> LDView<'a> {
+    const CHANNEL_META_PTR: usize = 8;
+    const CHANNEL_DATA_PTR: usize = 12;
+    const EVENT_PTR: usize = 36;
+    const DEVICE_SERIAL: usize = 70;
+    const DEVICE_TYPE: usize = 74;
+    const DEVICE_VERSION: usize = 82;
+    const DEVICE_FLAGS: usize = 84;
+    const NUM_CHANNELS: usize = 86;
+    const DATE: usize = 94;
+    const TIME: usize = 126;
+    const DRIVER: usize = 158;
+    const VEHICLE_ID: usize = 222;
+    const VENUE: usize = 350;
+    const SESSION: usize = 1508;
+    const SHORT_COMMENT: usize = 1572;
+
+    /// Creates a view over `bytes`, checking that it starts with a ld header
+    pub fn new(bytes: &'a [u8]) -> I2Result<Self> {
+        let view = Self { bytes };
+        let marker = view.u32_at(0)?;
+        if marker != LD_HEADER_MARKER {
+            return Err(I2Error::InvalidHeaderMarker {
+                found: marker,
+                expected: LD_HEADER_MARKER,
+            });
+        }
+        Ok(view)
+    }
+
+    pub fn channel_meta_ptr(&self) -> I2Result<u32> {
+        self.u32_at(Self::CHANNEL_META_PTR)
+    }
+
+    pub fn channel_data_ptr(&self) -> I2Result<u32> {
+        self.u32_at(Self::CHANNEL_DATA_PTR)
+    }
+
+    pub fn event_ptr(&self) -> I2Result<u32> {
+        self.u32_at(Self::EVENT_PTR)
+    }
+
+    pub fn device_serial(&self) -> I2Result<u32> {
+        self.u32_at(Self::DEVICE_SERIAL)
+    }
+
+    pub fn device_type(&self) -> I2Result<&'a str> {
+        self.str_at(Self::DEVICE_TYPE, 8)
+    }
+
+    pub fn device_version(&self) -> I2Result<u16> {
+        self.u16_at(Self::DEVICE_VERSION)
+    }
+
+    pub fn device_flags(&self) -> I2Result<u16> {
+        self.u16_at(Self::DEVICE_FLAGS)
+    }
+
+    pub fn num_channels(&self) -> I2Result<u32> {
+        self.u32_at(Self::NUM_CHANNELS)
+    }
+
+    pub fn date_string(&self) -> I2Result<&'a str> {
+        self.str_at(Self::DATE, 16)
+    }
+
+    pub fn time_string(&self) -> I2Result<&'a str> {
+        self.str_at(Self::TIME, 16)
+    }
+
+    pub fn driver(&self) -> I2Result<&'a str> {
+        self.str_at(Self::DRIVER, 64)
+    }
+
+    pub fn vehicleid(&self) -> I2Result<&'a str> {
+        self.str_at(Self::VEHICLE_ID, 64)
+    }
+
+    pub fn venue(&self) -> I2Result<&'a str> {
+        self.str_at(Self::VENUE, 64)
+    }
+
+    pub fn session(&self) -> I2Result<&'a str> {
+        self.str_at(Self::SESSION, 64)
+    }
+
+    pub fn short_comment(&self) -> I2Result<&'a str> {
+        self.str_at(Self::SHORT_COMMENT, 64)
+    }
+
+    /// Reads all of the header fields at once, borrowing the strings from the underlying buffer
+    ///
+    /// Unlike [crate::LDReader::read_header], strings that aren't valid UTF-8 don't cause an
+    /// error, invalid sequences are replaced with U+FFFD and only then the string is allocated.
+    pub fn header(&self) -> I2Result<HeaderRef<'a>> {
+        Ok(HeaderRef {
+            channel_meta_ptr: self.channel_meta_ptr()?,
+            channel_data_ptr: self.channel_data_ptr()?,
+            event_ptr: self.event_ptr()?,
+            device_serial: self.device_serial()?,
+            device_type: self.cow_at(Self::DEVICE_TYPE, 8)?,
+            device_version: self.device_version()?,
+            device_flags: self.device_flags()?,
+            num_channels: self.num_channels()?,
+            date_string: self.cow_at(Self::DATE, 16)?,
+            time_string: self.cow_at(Self::TIME, 16)?,
+            driver: self.cow_at(Self::DRIVER, 64)?,
+            vehicleid: self.cow_at(Self::VEHICLE_ID, 64)?,
+            venue: self.cow_at(Self::VENUE, 64)?,
+            session: self.cow_at(Self::SESSION, 64)?,
+            short_comment: self.cow_at(Self::SHORT_COMMENT, 64)?,
+        })
+    }
+
+    /// Iterates over the channel metadata list
+    ///
+    /// The list is followed as stored in the file, so a corrupted file with a loop in it will
+    /// produce channels forever. Use [crate::LDReader::channel_count] to check for that.
+    pub fn channels(&self) -> ChannelViews<'a> {
+        ChannelViews {
+            view: *self,
+            next_addr: self.channel_meta_ptr().ok(),
+        }
+    }
+
+    fn slice(&self, addr: usize, len: usize) -> I2Result<&'a [u8]> {
+        self.bytes
+            .get(addr..addr.saturating_add(len))
+            .ok_or(I2Error::UnexpectedEof {
+                while_reading: "field",
+                offset: addr as u64,
+            })
+    }
+
+    fn u16_at(&self, addr: usize) -> I2Result<u16> {
+        let bytes = self.slice(addr, 2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32_at(&self, addr: usize) -> I2Result<u32> {
+        let bytes = self.slice(addr, 4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// Reads a string with a fixed size trimming null bytes
+    fn str_at(&self, addr: usize, size: usize) -> I2Result<&'a str> {
+        let bytes = self.slice(addr, size)?;
+        let str_size = bytes.iter().position(|c| *c == b'\0').unwrap_or(size);
+        Ok(str::from_utf8(&bytes[..str_size])?)
+    }
+
+    /// Reads a string with a fixed size trimming null bytes, replacing invalid UTF-8
+    fn cow_at(&self, addr: usize, size: usize) -> I2Result<Cow<'a, str>> {
+        let bytes = self.slice(addr, size)?;
+        let str_size = bytes.iter().position(|c| *c == b'\0').unwrap_or(size);
+        Ok(String::from_utf8_lossy(&bytes[..str_size]))
+    }
+}
+
+/// The fields of a [crate::Header], with strings borrowed from a [LDView] when possible
+///
+/// See [LDView::header]
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct HeaderRef<'a> {
+    pub channel_meta_ptr: u32,
+    pub channel_data_ptr: u32,
+    pub event_ptr: u32,
+
+    pub device_serial: u32,
+    pub device_type: Cow<'a, str>,
+    pub device_version: u16,
+    pub device_flags: u16,
+
+    pub num_channels: u32,
+
+    pub date_string: Cow<'a, str>,
+    pub time_string: Cow<'a, str>,
+
+    pub driver: Cow<'a, str>,
+    pub vehicleid: Cow<'a, str>,
+    pub venue: Cow<'a, str>,
+    pub session: Cow<'a, str>,
+    pub short_comment: Cow<'a, str>,
+}
+
+#[cfg(feature = "std")]
+impl HeaderRef<'_> {
+    /// Copies the strings to build an owned [crate::Header]
+    pub fn into_owned(self) -> crate::Header {
+        crate::Header {
+            channel_meta_ptr: self.channel_meta_ptr,
+            channel_data_ptr: self.channel_data_ptr,
+            event_ptr: self.event_ptr,
+            device_serial: self.device_serial,
+            device_type: self.device_type.into_owned(),
+            device_version: self.device_version,
+            device_flags: self.device_flags,
+            num_channels: self.num_channels,
+            date_string: self.date_string.into_owned(),
+            time_string: self.time_string.into_owned(),
+            driver: self.driver.into_owned(),
+            vehicleid: self.vehicleid.into_owned(),
+            venue: self.venue.into_owned(),
+            session: self.session.into_owned(),
+            short_comment: self.short_comment.into_owned(),
+        }
+    }
+}
+
+/// Iterator over the channels of a [LDView], see [LDView::channels]
+#[derive(Debug, Clone)]
+pub struct ChannelViews<'a> {
+    view: LDView<'a>,
+    next_addr: Option<u32>,
+}
+
+impl<'a> Iterator for ChannelViews<'a> {
+    type Item = I2Result<ChannelView<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // A 0 addr means we are done with the list
+        let addr = self.next_addr.filter(|addr| *addr != 0)?;
+        let channel = ChannelView {
+            view: self.view,
+            addr: addr as usize,
+        };
+
+        match channel.next_addr() {
+            Ok(next) => self.next_addr = Some(next),
+            Err(e) => {
+                self.next_addr = None;
+                return Some(Err(e));
+            }
+        }
+        Some(Ok(channel))
+    }
+}
+
+/// A borrowed channel metadata entry, see [crate::ChannelMetadata]
+#[derive(Debug, Clone, Copy)]
+pub struct ChannelView<'a> {
+    view: LDView<'a>,
+    addr: usize,
+}
+
+impl<'a> ChannelView<'a> {
+    pub fn prev_addr(&self) -> I2Result<u32> {
+        self.view.u32_at(self.addr)
+    }
+
+    pub fn next_addr(&self) -> I2Result<u32> {
+        self.view.u32_at(self.addr + 4)
+    }
+
+    pub fn data_addr(&self) -> I2Result<u32> {
+        self.view.u32_at(self.addr + 8)
+    }
+
+    pub fn data_count(&self) -> I2Result<u32> {
+        self.view.u32_at(self.addr + 12)
+    }
+
+    pub fn datatype(&self) -> I2Result<Datatype> {
+        let _type = self.view.u16_at(self.addr + 18)?;
+        let size = self.view.u16_at(self.addr + 20)?;
+        Datatype::from_type_and_size(_type, size)
+    }
+
+    /// Sample Rate in Hz
+    pub fn sample_rate(&self) -> I2Result<u16> {
+        self.view.u16_at(self.addr + 22)
+    }
+
+    pub fn name(&self) -> I2Result<&'a str> {
+        self.view.str_at(self.addr + 32, 32)
+    }
+
+    pub fn short_name(&self) -> I2Result<&'a str> {
+        self.view.str_at(self.addr + 64, 8)
+    }
+
+    pub fn unit(&self) -> I2Result<&'a str> {
+        self.view.str_at(self.addr + 72, 12)
+    }
+
+    /// The raw, undecoded, data section of this channel
+    ///
+    /// Channels with a 0 data pointer, or in a file with a 0 [LDView::channel_data_ptr], have
+    /// no data.
+    pub fn data(&self) -> I2Result<&'a [u8]> {
+        let data_addr = self.data_addr()?;
+        if data_addr == 0 || self.view.channel_data_ptr()? == 0 {
+            return Ok(&[]);
+        }
+
+        let len = self.data_count()? as usize * self.datatype()?.size() as usize;
+        self.view.slice(data_addr as usize, len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Datatype, I2Error, LDView};
+    use alloc::borrow::Cow;
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+    use std::fs;
+
+    /// Counts the allocations made by the current thread, so that other tests running in
+    /// parallel don't interfere
+    struct CountingAllocator;
+
+    thread_local! {
+        static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            ALLOCATIONS.with(|a| a.set(a.get() + 1));
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    fn allocations() -> usize {
+        ALLOCATIONS.with(|a| a.get())
+    }
+
+    #[test]
+    fn sample1_view() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let view = LDView::new(&bytes).unwrap();
+
+        let channels: Vec<_> = view.channels().collect::<Result<_, _>>().unwrap();
+        assert_eq!(channels.len(), 78);
+        assert_eq!(channels[1].name().unwrap(), "Brake Temp FL");
+        assert_eq!(channels[1].unit().unwrap(), "C");
+        assert_eq!(channels[1].data().unwrap().len(), 4540 * 2);
+    }
+
+    /// Only uses what is available without the `std` feature
+    #[test]
+    fn sample1_view_without_std() {
+        static SAMPLE1: &[u8] = include_bytes!("../samples/Sample1.ld");
+        let view = LDView::new(SAMPLE1).unwrap();
+        assert_eq!(view.date_string().unwrap(), "23/11/2005");
+        assert_eq!(view.time_string().unwrap(), "09:53:00");
+
+        let channel = view.channels().next().unwrap().unwrap();
+        assert_eq!(channel.name().unwrap(), "Air Temp Inlet");
+        assert_eq!(channel.datatype().unwrap(), Datatype::I16);
+        assert_eq!(channel.sample_rate().unwrap(), 2);
+        assert_eq!(channel.data_count().unwrap(), 908);
+
+        assert!(matches!(
+            LDView::new(&SAMPLE1[..2]),
+            Err(I2Error::UnexpectedEof { offset: 0, .. })
+        ));
+    }
+
+    /// Compares the allocations made parsing the Sample1.ld header with [crate::LDReader] and
+    /// with [LDView::header]
+    #[cfg(feature = "std")]
+    #[test]
+    fn borrowed_header_allocations() {
+        use crate::LDReader;
+        use std::io::Cursor;
+
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+
+        let mut cursor = Cursor::new(&bytes[..]);
+        let mut reader = LDReader::new(&mut cursor);
+        let before = allocations();
+        let owned = reader.read_header().unwrap();
+        let owned_allocations = allocations() - before;
+
+        let before = allocations();
+        let view = LDView::new(&bytes).unwrap();
+        let borrowed = view.header().unwrap();
+        let borrowed_allocations = allocations() - before;
+
+        // The owned header reads each of its 8 strings into a buffer of its own
+        assert!(owned_allocations >= 8, "{owned_allocations} allocations");
+        assert_eq!(borrowed_allocations, 0);
+        assert!(matches!(borrowed.venue, Cow::Borrowed("Calder")));
+        assert_eq!(borrowed.into_owned(), owned);
+    }
+
+    #[test]
+    fn borrowed_header_invalid_utf8() {
+        let mut bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let venue = 350;
+        bytes[venue + 1] = 0xFF;
+
+        let view = LDView::new(&bytes).unwrap();
+        assert!(view.venue().is_err());
+        let header = view.header().unwrap();
+        assert!(matches!(header.venue, Cow::Owned(_)));
+        assert_eq!(header.venue, "C\u{FFFD}lder");
+        assert!(matches!(header.driver, Cow::Borrowed("")));
+    }
+
+    #[test]
+    fn header_scan_does_not_allocate() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+
+        let before = allocations();
+        let view = LDView::new(&bytes).unwrap();
+        assert_eq!(view.device_type().unwrap(), "ADL");
+        assert_eq!(view.venue().unwrap(), "Calder");
+        assert_eq!(view.vehicleid().unwrap(), "11A");
+        assert_eq!(view.short_comment().unwrap(), "second warmup");
+        assert_eq!(view.num_channels().unwrap(), 78);
+
+        let mut data_len = 0;
+        for channel in view.channels() {
+            let channel = channel.unwrap();
+            assert!(!channel.name().unwrap().is_empty());
+            data_len += channel.data().unwrap().len();
+        }
+        assert!(data_len > 0);
+        assert_eq!(allocations(), before);
+    }
+}
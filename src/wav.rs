@@ -0,0 +1,76 @@
+use crate::{ChannelMetadata, I2Result, LDReader};
+use std::io::{Read, Seek, Write};
+
+/// Exports a channel as a mono 16 bit WAV file
+///
+/// The decoded values have their mean removed and are normalized so that the peak value uses
+/// the full i16 range. If `sample_rate_hz` is `None` the WAV file uses the channel's logging rate,
+/// otherwise the channel is resampled to that rate, holding each value until the next sample.
+pub fn export_channel_wav<S: Read + Seek, W: Write + Seek>(
+    reader: &mut LDReader<S>,
+    channel: &ChannelMetadata,
+    out: W,
+    sample_rate_hz: Option<u32>,
+) -> I2Result<()> {
+    let values = reader.decode_channel(channel)?.values;
+
+    let channel_rate = channel.sample_rate as u32;
+    let rate = sample_rate_hz.unwrap_or(channel_rate);
+    let values: Vec<f64> = if rate == channel_rate || channel_rate == 0 {
+        values
+    } else {
+        let len = values.len() as u64 * rate as u64 / channel_rate as u64;
+        (0..len)
+            .map(|i| values[(i * channel_rate as u64 / rate as u64) as usize])
+            .collect()
+    };
+
+    let mean = values.iter().sum::<f64>() / values.len().max(1) as f64;
+    let peak = values.iter().map(|v| (v - mean).abs()).fold(0.0, f64::max);
+    let gain = if peak == 0.0 {
+        0.0
+    } else {
+        i16::MAX as f64 / peak
+    };
+
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: rate,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut writer = hound::WavWriter::new(out, spec)?;
+    for value in values {
+        writer.write_sample(((value - mean) * gain).round() as i16)?;
+    }
+    writer.finalize()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{export_channel_wav, LDReader};
+    use std::fs;
+    use std::io::Cursor;
+
+    #[test]
+    fn export_sample1_wav() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut cursor = Cursor::new(bytes);
+        let mut reader = LDReader::new(&mut cursor);
+
+        let channels = reader.read_channels().unwrap();
+        let channel = &channels[77];
+
+        let mut wav = Cursor::new(Vec::new());
+        export_channel_wav(&mut reader, channel, &mut wav, None).unwrap();
+        wav.set_position(0);
+
+        let wav = hound::WavReader::new(wav).unwrap();
+        assert_eq!(wav.spec().sample_rate, 20);
+        assert_eq!(wav.spec().channels, 1);
+        assert_eq!(wav.spec().bits_per_sample, 16);
+        assert_eq!(wav.len(), 9080);
+    }
+}
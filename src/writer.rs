@@ -1,14 +1,67 @@
 use crate::full_header::FULL_HEADER;
-use crate::{ChannelMetadata, Header, I2Result, Sample, LD_HEADER_MARKER};
+use crate::structs::f32_to_f16;
+use crate::{
+    ChannelLayout, ChannelMetadata, Datatype, Event, Header, I2Error, I2Result, LDReader,
+    OverflowPolicy, Sample, Vehicle, Venue, WrittenLayout, EXTENSION_MARKER, LD_HEADER_MARKER,
+};
 use byteorder::{LittleEndian, WriteBytesExt};
-use core::iter;
-use std::io::{Seek, SeekFrom, Write};
+use core::{iter, mem};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+/// Offset of [Header::channel_meta_ptr] in the file
+const CHANNEL_META_PTR_OFFSET: u64 = 8;
+/// Offset of [Header::channel_data_ptr] in the file
+const CHANNEL_DATA_PTR_OFFSET: u64 = 12;
+/// Offset of [Header::event_ptr] in the file
+const EVENT_PTR_OFFSET: u64 = 36;
+/// Offset of [Header::num_channels] in the file
+const NUM_CHANNELS_OFFSET: u64 = 86;
+
+/// Where the venue is written, see [LDWriter::with_venue]
+///
+/// This is inside of the header area, since [Event::venue_addr] is only 16 bits. It's the same
+/// place as in Sample1.ld.
+const VENUE_ADDR: u16 = 0x1336;
+/// Where the vehicle is written, see [LDWriter::with_vehicle]
+///
+/// Like [VENUE_ADDR], this is the same place as in Sample1.ld.
+const VEHICLE_ADDR: u16 = 0x1F54;
+
+/// Offset of [ChannelMetadata::next_addr] inside a metadata entry
+const NEXT_ADDR_OFFSET: u32 = 4;
+/// Offset of [ChannelMetadata::data_count] inside a metadata entry
+const DATA_COUNT_OFFSET: u32 = 12;
+/// Offset of [ChannelMetadata::dec_places] inside a metadata entry
+const DEC_PLACES_OFFSET: u32 = 30;
 
 #[derive(Debug)]
 pub struct LDWriter<'a, S: Write + Seek> {
     sink: &'a mut S,
     header: Header,
     channels: Vec<(ChannelMetadata, Vec<Sample>)>,
+    overflow_policy: OverflowPolicy,
+    /// Minimum size of the output, see [LDWriter::pad_to]
+    pad_to: Option<u64>,
+    /// Key value pairs written after the data sections, see [LDWriter::set_extension]
+    extensions: Vec<(String, String)>,
+    /// Written right after the header, see [LDWriter::with_event]
+    event: Option<Event>,
+    /// See [LDWriter::with_venue]
+    venue: Option<Venue>,
+    /// See [LDWriter::with_vehicle]
+    vehicle: Option<Vehicle>,
+
+    // State for the block writing API, see [LDWriter::begin_channel]
+    /// Offset where the next block of data is going to be written, 0 if the header
+    /// hasn't been written yet
+    end_addr: u32,
+    /// Address of the last channel metadata entry that was written
+    last_meta_addr: Option<u32>,
+    /// Number of channels written with [LDWriter::begin_channel]
+    block_channels: u32,
+
+    /// Where the channels written so far ended up
+    layout: WrittenLayout,
 }
 
 impl<'a, S: Write + Seek> LDWriter<'a, S> {
@@ -17,6 +70,16 @@ impl<'a, S: Write + Seek> LDWriter<'a, S> {
             sink,
             header,
             channels: Vec::new(),
+            overflow_policy: OverflowPolicy::default(),
+            pad_to: None,
+            extensions: Vec::new(),
+            event: None,
+            venue: None,
+            vehicle: None,
+            end_addr: 0,
+            last_meta_addr: None,
+            block_channels: 0,
+            layout: WrittenLayout::default(),
         }
     }
 
@@ -25,17 +88,366 @@ impl<'a, S: Write + Seek> LDWriter<'a, S> {
         self
     }
 
-    pub fn write(mut self) -> I2Result<()> {
-        // TODO: Fix these clones
+    /// Writes `event` right after the header, pointing [Header::event_ptr] to it
+    ///
+    /// Without an event, [Header::event_ptr] is written as is. [Event::venue_addr] is replaced
+    /// with the address of the venue, or 0 if there isn't one, see [LDWriter::with_venue].
+    pub fn with_event(mut self, event: Event) -> Self {
+        self.event = Some(event);
+        self
+    }
+
+    /// Writes `venue` and points the event's [Event::venue_addr] to it
+    ///
+    /// The venue is only reachable through the event, so it isn't written unless
+    /// [LDWriter::with_event] is also used.
+    ///
+    /// [Venue::vehicle_addr] is replaced with the address of the vehicle, or 0 if there isn't
+    /// one, see [LDWriter::with_vehicle].
+    pub fn with_venue(mut self, venue: Venue) -> Self {
+        self.venue = Some(venue);
+        self
+    }
+
+    /// Writes `vehicle` and points the venue's [Venue::vehicle_addr] to it
+    ///
+    /// The vehicle is only reachable through the venue, so it isn't written unless
+    /// [LDWriter::with_venue] is also used.
+    pub fn with_vehicle(mut self, vehicle: Vehicle) -> Self {
+        self.vehicle = Some(vehicle);
+        self
+    }
+
+    /// Sets what happens when writing physical values that don't fit in a channel's datatype
+    ///
+    /// Defaults to [OverflowPolicy::Error]
+    pub fn set_overflow_policy(&mut self, policy: OverflowPolicy) {
+        self.overflow_policy = policy;
+    }
+
+    /// Zero fills the output so that it is at least `size` bytes long
+    ///
+    /// Writing fails with [I2Error::PaddingTooSmall] if the content doesn't fit in `size`. When
+    /// using [LDWriter::begin_channel] the padding is redone after each channel.
+    pub fn pad_to(mut self, size: u64) -> Self {
+        self.pad_to = Some(size);
+        self
+    }
+
+    /// Stores a custom key value pair in a block after the data sections
+    ///
+    /// i2 ignores this block, use [LDReader::extensions] to read it back. Setting the same key
+    /// again replaces its value. When using [LDWriter::begin_channel] the block is rewritten
+    /// after each channel.
+    pub fn set_extension(&mut self, key: &str, value: &str) {
+        match self.extensions.iter_mut().find(|(k, _)| k == key) {
+            Some((_, v)) => *v = value.to_string(),
+            None => self.extensions.push((key.to_string(), value.to_string())),
+        }
+    }
+
+    /// Writes the header and all channels
+    ///
+    /// [Header::num_channels] and the channel pointers of the header are filled in from the
+    /// channels, so they don't need to be set beforehand.
+    pub fn write(self) -> I2Result<()> {
+        self.write_with_layout()?;
+        Ok(())
+    }
+
+    /// Writes the file like [LDWriter::write], returning where each channel was placed
+    ///
+    /// This allows building an index of the file without reading it back.
+    pub fn write_with_layout(mut self) -> I2Result<WrittenLayout> {
+        let channels = mem::take(&mut self.channels);
         self.write_header(&self.header.clone())?;
-        self.write_channels(self.channels.clone())?;
+        self.write_channels(channels)?;
+
+        let data_end = self
+            .layout
+            .channels
+            .iter()
+            .map(|c| c.data_addr + c.data_size)
+            .max()
+            .unwrap_or(FULL_HEADER.len() as u32);
+        self.write_extensions(data_end)?;
+        self.pad()?;
+        Ok(self.layout)
+    }
+
+    /// Where the channels written so far with [LDWriter::begin_channel] were placed
+    pub fn layout(&self) -> &WrittenLayout {
+        &self.layout
+    }
+
+    /// Writes the extension block at `addr`, if there are any extensions
+    ///
+    /// The block starts with [EXTENSION_MARKER] and the number of entries as a u32, followed by
+    /// each key and value prefixed with their length in bytes as a u32.
+    fn write_extensions(&mut self, addr: u32) -> I2Result<()> {
+        if self.extensions.is_empty() {
+            return Ok(());
+        }
+
+        self.sink.seek(SeekFrom::Start(addr as u64))?;
+        self.sink.write_all(&EXTENSION_MARKER)?;
+        self.sink
+            .write_u32::<LittleEndian>(self.extensions.len() as u32)?;
+        for (key, value) in self.extensions.iter() {
+            for string in [key, value] {
+                self.sink.write_u32::<LittleEndian>(string.len() as u32)?;
+                self.sink.write_all(string.as_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies the padding set with [LDWriter::pad_to]
+    fn pad(&mut self) -> I2Result<()> {
+        let Some(size) = self.pad_to else {
+            return Ok(());
+        };
+
+        let content_size = self.sink.seek(SeekFrom::End(0))?;
+        if content_size > size {
+            return Err(I2Error::PaddingTooSmall { size, content_size });
+        }
+        io::copy(&mut io::repeat(0).take(size - content_size), self.sink)?;
+        Ok(())
+    }
+
+    /// Starts writing a channel whose samples are supplied in blocks
+    ///
+    /// This allows writing channels without holding all of their samples in memory. The header
+    /// is written on the first call, and each channel's metadata entry is placed right before
+    /// its data section, with the linked list being patched as channels are added.
+    ///
+    /// [ChannelHandle::finish] must be called before beginning the next channel.
+    pub fn begin_channel(
+        &mut self,
+        mut channel: ChannelMetadata,
+    ) -> I2Result<ChannelHandle<'_, 'a, S>> {
+        let meta_addr = self.link_channel(&mut channel)?;
+        Ok(ChannelHandle {
+            writer: self,
+            meta_addr,
+            channel,
+            raw_range: None,
+        })
+    }
+
+    /// Starts writing channels whose samples arrive interleaved, such as live telemetry
+    ///
+    /// Each channel's data has to be contiguous in the file, so every channel registered with
+    /// [StreamWriter::register_channel] reserves room for `max_duration_secs` worth of samples
+    /// at its sample rate. The header is written right away, and the sample counts are patched
+    /// in by [StreamWriter::finalize].
+    pub fn begin_stream(&mut self, max_duration_secs: u32) -> I2Result<StreamWriter<'_, 'a, S>> {
+        self.write_header_once()?;
+        Ok(StreamWriter {
+            writer: self,
+            max_duration_secs,
+            channels: Vec::new(),
+        })
+    }
+
+    /// Writes the header on the first call of the block writing API
+    fn write_header_once(&mut self) -> I2Result<()> {
+        if self.end_addr == 0 {
+            self.write_header(&self.header.clone())?;
+            self.end_addr = FULL_HEADER.len() as u32;
+        }
+        Ok(())
+    }
+
+    /// Writes the metadata entry of `channel` at the end of the file, with its data section
+    /// right after it, and links it from the previous channel, returning the entry's address
+    fn link_channel(&mut self, channel: &mut ChannelMetadata) -> I2Result<u32> {
+        self.write_header_once()?;
+
+        let meta_addr = self.end_addr;
+        channel.prev_addr = self.last_meta_addr.unwrap_or(0);
+        channel.next_addr = 0;
+        channel.data_addr = meta_addr + ChannelMetadata::ENTRY_SIZE;
+        channel.data_count = 0;
+        self.write_channel_metadata(meta_addr, channel, None)?;
+
+        match self.last_meta_addr {
+            Some(prev) => {
+                self.sink
+                    .seek(SeekFrom::Start((prev + NEXT_ADDR_OFFSET) as u64))?;
+                self.sink.write_u32::<LittleEndian>(meta_addr)?;
+            }
+            None => {
+                self.sink.seek(SeekFrom::Start(CHANNEL_META_PTR_OFFSET))?;
+                self.sink.write_u32::<LittleEndian>(meta_addr)?;
+                self.sink.seek(SeekFrom::Start(CHANNEL_DATA_PTR_OFFSET))?;
+                self.sink.write_u32::<LittleEndian>(channel.data_addr)?;
+            }
+        }
+
+        self.last_meta_addr = Some(meta_addr);
+        self.end_addr = channel.data_addr;
+        Ok(meta_addr)
+    }
+
+    /// Patches the sample count and raw range of a channel written with [LDWriter::link_channel]
+    /// into its metadata entry, and the number of channels into the header
+    fn finish_channel(
+        &mut self,
+        meta_addr: u32,
+        channel: &ChannelMetadata,
+        raw_range: Option<(i32, i32)>,
+    ) -> I2Result<()> {
+        let trailer_addr = meta_addr + ChannelMetadata::FIXED_SIZE;
+        self.sink.seek(SeekFrom::Start(trailer_addr as u64))?;
+        self.write_raw_range(raw_range)?;
+
+        self.sink
+            .seek(SeekFrom::Start((meta_addr + DATA_COUNT_OFFSET) as u64))?;
+        self.sink.write_u32::<LittleEndian>(channel.data_count)?;
+
+        self.block_channels += 1;
+        self.sink.seek(SeekFrom::Start(NUM_CHANNELS_OFFSET))?;
+        self.sink.write_u32::<LittleEndian>(self.block_channels)?;
+
+        self.layout.channels.push(ChannelLayout {
+            name: channel.name.clone(),
+            meta_addr,
+            data_addr: channel.data_addr,
+            data_size: channel.data_size(),
+        });
+        Ok(())
+    }
+
+    /// Adds a channel to the end of an existing ld file
+    ///
+    /// The channel's metadata and samples are written at the end of `file`, and linked from the
+    /// last channel in its channel list. [Header::num_channels] is updated to the number of
+    /// channels in the list. The extension block of the file, if any, is no longer found by
+    /// [LDReader::extensions] afterwards, since it isn't after the last data section anymore.
+    pub fn append_channel(
+        file: &'a mut S,
+        channel: &ChannelMetadata,
+        data: &[Sample],
+    ) -> I2Result<()>
+    where
+        S: Read,
+    {
+        let mut reader = LDReader::new(file);
+        let header = reader.read_header()?;
+        let channels = reader.read_channels()?;
+
+        // Each channel's addr is stored in the previous one, or in the header for the first one
+        let last_meta_addr = match channels.len() {
+            0 => None,
+            1 => Some(header.channel_meta_ptr),
+            n => Some(channels[n - 2].next_addr),
+        };
+
+        let end_addr = file.seek(SeekFrom::End(0))? as u32;
+        let mut writer = LDWriter::new(file, header);
+        writer.end_addr = end_addr.max(FULL_HEADER.len() as u32);
+        writer.last_meta_addr = last_meta_addr;
+        writer.block_channels = channels.len() as u32;
+
+        let mut handle = writer.begin_channel(channel.clone())?;
+        handle.write_block(data)?;
+        handle.finish()
+    }
+
+    /// Edits the names, units and decimal places of the channels of an existing ld file in place
+    ///
+    /// `edit` is called with the metadata of every channel in the file, in order. Only
+    /// [ChannelMetadata::name], [ChannelMetadata::short_name], [ChannelMetadata::unit] and
+    /// [ChannelMetadata::dec_places] are written back, changes to any other field are ignored.
+    /// These have a fixed size in the metadata entry, so nothing else in the file is moved and
+    /// the sample data is never read or written.
+    pub fn edit_channels(
+        file: &'a mut S,
+        mut edit: impl FnMut(&mut ChannelMetadata),
+    ) -> I2Result<()>
+    where
+        S: Read,
+    {
+        let mut reader = LDReader::new(file);
+        let header = reader.read_header()?;
+        let channels = reader.read_channels()?;
+
+        // Each channel's addr is stored in the previous one, or in the header for the first one
+        let meta_addrs: Vec<u32> = iter::once(header.channel_meta_ptr)
+            .chain(channels.iter().map(|c| c.next_addr))
+            .collect();
+
+        let mut writer = LDWriter::new(file, header);
+        for (mut channel, addr) in channels.into_iter().zip(meta_addrs) {
+            edit(&mut channel);
+
+            writer
+                .sink
+                .seek(SeekFrom::Start((addr + DEC_PLACES_OFFSET) as u64))?;
+            writer.sink.write_i16::<LittleEndian>(channel.dec_places)?;
+            writer.write_string(32, &channel.name)?;
+            writer.write_string(8, &channel.short_name)?;
+            writer.write_string(12, &channel.unit)?;
+        }
+        Ok(())
+    }
+
+    /// Writes a channel of physical values, picking a datatype and scaling for them
+    ///
+    /// The smallest number of decimal places (up to 6) that represents all values exactly is
+    /// used, the values are then stored as [Datatype::I16] if they fit, or as [Datatype::I32].
+    /// Values that can't be represented as integers are stored as [Datatype::F32].
+    ///
+    /// This uses the block writing API, see [LDWriter::begin_channel].
+    pub fn log(&mut self, name: &str, unit: &str, hz: u16, values: &[f64]) -> I2Result<()> {
+        let (datatype, dec_places) = pick_datatype(values);
+        let channel = ChannelMetadata {
+            prev_addr: 0,
+            next_addr: 0,
+            data_addr: 0,
+            data_count: 0,
+            datatype,
+            sample_rate: hz,
+            offset: 0,
+            mul: 1,
+            scale: 1,
+            dec_places,
+            name: name.to_string(),
+            short_name: name.to_string(),
+            unit: unit.to_string(),
+        };
+
+        let mut handle = self.begin_channel(channel)?;
+        handle.write_block_f64(values)?;
+        handle.finish()
+    }
+
+    /// Copies the channels of `reader` for which `channel_filter` returns true
+    ///
+    /// Channels are copied one at a time using the block writing API, so only a single
+    /// channel's samples are held in memory at any point.
+    pub fn copy_from<R: Read + Seek>(
+        &mut self,
+        reader: &mut LDReader<R>,
+        channel_filter: impl Fn(&ChannelMetadata) -> bool,
+    ) -> I2Result<()> {
+        let mut samples = Vec::new();
+        for channel in reader.read_channels_filtered(channel_filter)? {
+            reader.channel_data_into(&channel, &mut samples)?;
+
+            let mut handle = self.begin_channel(channel)?;
+            handle.write_block(&samples)?;
+            handle.finish()?;
+        }
         Ok(())
     }
 
     fn write_header(&mut self, hdr: &Header) -> I2Result<()> {
         // See comments on FULL_HEADER for an explanation on why we do this.
         self.sink.seek(SeekFrom::Start(0))?;
-        self.sink.write(&FULL_HEADER[..])?;
+        self.sink.write_all(&FULL_HEADER[..])?;
 
         // Header is always at start
         self.sink.seek(SeekFrom::Start(0))?;
@@ -49,13 +461,13 @@ impl<'a, S: Write + Seek> LDWriter<'a, S> {
         self.sink.write_u32::<LittleEndian>(hdr.channel_data_ptr)?;
 
         // TODO: We don't know what this is, but Sample1.ld has it as 0
-        self.sink.write(&[0u8; 20][..])?;
+        self.sink.write_all(&[0u8; 20][..])?;
 
         self.sink.write_u32::<LittleEndian>(hdr.event_ptr)?;
 
         // TODO: We don't know what this is, but Sample1.ld has it as 0
         // 20160903-0051401.ld has this as a different value
-        self.sink.write(&[0u8; 24][..])?;
+        self.sink.write_all(&[0u8; 24][..])?;
 
         // TODO: We don't know what these are...
         self.sink.write_u16::<LittleEndian>(0x0000)?;
@@ -66,8 +478,7 @@ impl<'a, S: Write + Seek> LDWriter<'a, S> {
         self.write_string(8, &hdr.device_type)?;
         self.sink.write_u16::<LittleEndian>(hdr.device_version)?;
 
-        // TODO: We don't know what this is, but Sample1.ld has it as this const
-        self.sink.write_u16::<LittleEndian>(0x0080)?;
+        self.sink.write_u16::<LittleEndian>(hdr.device_flags)?;
 
         self.sink.write_u32::<LittleEndian>(hdr.num_channels)?;
         // TODO: We don't know what this is, but Sample1.ld has it as this const
@@ -84,7 +495,7 @@ impl<'a, S: Write + Seek> LDWriter<'a, S> {
         self.write_string(64, &hdr.venue)?;
         self.write_string(64, "")?;
 
-        self.sink.write(&[0u8; 1024])?;
+        self.sink.write_all(&[0u8; 1024])?;
 
         // 0xD20822 for Sample1.ld
         // ProLogging related
@@ -94,46 +505,98 @@ impl<'a, S: Write + Seek> LDWriter<'a, S> {
         self.write_string(64, &hdr.session)?;
         self.write_string(64, &hdr.short_comment)?;
 
-        self.sink.write(&[0u8; 8])?;
+        self.sink.write_all(&[0u8; 8])?;
         self.sink.write_u8(99)?;
-        self.sink.write(&[0u8; 117])?;
+        self.sink.write_all(&[0u8; 117])?;
+
+        if let Some(event) = self.event.clone() {
+            let event_ptr = self.sink.stream_position()? as u32;
+            self.write_event(&event)?;
+
+            let venue_addr = match self.venue.clone() {
+                Some(venue) => {
+                    self.sink.seek(SeekFrom::Start(VENUE_ADDR as u64))?;
+                    self.write_venue(&venue)?;
+
+                    let vehicle_addr = match self.vehicle.clone() {
+                        Some(vehicle) => {
+                            self.sink.seek(SeekFrom::Start(VEHICLE_ADDR as u64))?;
+                            self.write_vehicle(&vehicle)?;
+                            VEHICLE_ADDR
+                        }
+                        None => 0,
+                    };
+                    self.sink.seek(SeekFrom::Start(
+                        (VENUE_ADDR as u32 + Venue::VEHICLE_ADDR_OFFSET) as u64,
+                    ))?;
+                    self.sink.write_u16::<LittleEndian>(vehicle_addr)?;
+
+                    VENUE_ADDR
+                }
+                None => 0,
+            };
+            self.sink.seek(SeekFrom::Start(
+                (event_ptr + Event::VENUE_ADDR_OFFSET) as u64,
+            ))?;
+            self.sink.write_u16::<LittleEndian>(venue_addr)?;
+
+            self.sink.seek(SeekFrom::Start(EVENT_PTR_OFFSET))?;
+            self.sink.write_u32::<LittleEndian>(event_ptr)?;
+            self.header.event_ptr = event_ptr;
+        }
+
+        Ok(())
+    }
+
+    fn write_event(&mut self, event: &Event) -> I2Result<()> {
+        self.write_string(64, &event.name)?;
+        self.write_string(64, &event.session)?;
+        self.write_string(1024, &event.comment)?;
+        self.sink.write_u16::<LittleEndian>(event.venue_addr)?;
+        Ok(())
+    }
 
-        // TODO: Write Event
+    /// Writes `venue`, with [Venue::raw_extra] zero padded or truncated to its size
+    fn write_venue(&mut self, venue: &Venue) -> I2Result<()> {
+        self.write_string(64, &venue.name)?;
+        let mut raw_extra = venue.raw_extra.clone();
+        raw_extra.resize(Venue::RAW_EXTRA_SIZE, 0);
+        self.sink.write_all(&raw_extra)?;
+        self.sink.write_u16::<LittleEndian>(venue.vehicle_addr)?;
+        Ok(())
+    }
 
+    fn write_vehicle(&mut self, vehicle: &Vehicle) -> I2Result<()> {
+        self.write_string(64, &vehicle.id)?;
+        // TODO: We don't know what this is, the reader skips it
+        self.sink.write_all(&[0u8; 128])?;
+        self.sink.write_u32::<LittleEndian>(vehicle.weight)?;
+        self.write_string(32, &vehicle._type)?;
+        self.write_string(32, &vehicle.comment)?;
         Ok(())
     }
 
+    /// Writes the channel metadata list right after the header, followed by the data sections
+    /// of each channel in the same order
+    ///
+    /// The channel pointers and [Header::num_channels] are patched in the header to match.
     fn write_channels(&mut self, channels: Vec<(ChannelMetadata, Vec<Sample>)>) -> I2Result<()> {
-        let meta_addrs: Vec<u32> = channels
-            .iter()
-            .enumerate()
-            .map(|(i, _)| {
-                // TODO: Should not be hardcoded
-                let header = 0x3448;
-                let meta_offset = i * ChannelMetadata::ENTRY_SIZE as usize;
-                (header + meta_offset) as u32
-            })
-            .collect();
+        for (channel, samples) in channels.iter() {
+            check_sample_datatypes(channel, samples)?;
+        }
 
-        let sample_byte_sizes: Vec<u32> = channels
-            .iter()
-            .map(|(channel, samples)| {
-                // TODO: ........ dont do this...
-                let mut channel = channel.clone();
-                channel.data_count = samples.len() as u32;
-                channel.data_size()
-            })
+        let meta_base = FULL_HEADER.len() as u32;
+        let meta_addrs: Vec<u32> = (0..channels.len() as u32)
+            .map(|i| meta_base + i * ChannelMetadata::ENTRY_SIZE)
             .collect();
 
+        let data_base = meta_base + channels.len() as u32 * ChannelMetadata::ENTRY_SIZE;
         let sample_addrs: Vec<u32> = channels
             .iter()
-            .enumerate()
-            .map(|(i, (_, _))| {
-                let header = 0x3448;
-                let meta_offset = channels.len() * ChannelMetadata::ENTRY_SIZE as usize;
-                let sample_offset = sample_byte_sizes.iter().take(i).sum::<u32>() as usize;
-
-                (header + meta_offset + sample_offset) as u32
+            .scan(data_base, |addr, (channel, samples)| {
+                let channel_addr = *addr;
+                *addr += samples.len() as u32 * channel.datatype.size() as u32;
+                Some(channel_addr)
             })
             .collect();
 
@@ -154,17 +617,48 @@ impl<'a, S: Write + Seek> LDWriter<'a, S> {
             channel.next_addr = meta_addrs.get(i + 1).copied().unwrap_or(0);
             channel.data_count = samples.len() as u32;
             channel.data_addr = *sample_addr;
-            self.write_channel_metadata(*meta_addr, &channel)?;
+            self.write_channel_metadata(*meta_addr, &channel, raw_range(samples))?;
+            self.layout.channels.push(ChannelLayout {
+                name: channel.name.clone(),
+                meta_addr: *meta_addr,
+                data_addr: channel.data_addr,
+                data_size: channel.data_size(),
+            });
         }
 
         for ((_, samples), sample_addr) in channels.iter().zip(sample_addrs) {
             self.write_samples(sample_addr, samples)?;
         }
 
+        // Without any channels the pointers are 0, which is the end of the channel list
+        let (meta_ptr, data_ptr) = if channels.is_empty() {
+            (0, 0)
+        } else {
+            (meta_base, data_base)
+        };
+        self.header.channel_meta_ptr = meta_ptr;
+        self.header.channel_data_ptr = data_ptr;
+        self.header.num_channels = channels.len() as u32;
+
+        self.sink.seek(SeekFrom::Start(CHANNEL_META_PTR_OFFSET))?;
+        self.sink.write_u32::<LittleEndian>(meta_ptr)?;
+        self.sink.seek(SeekFrom::Start(CHANNEL_DATA_PTR_OFFSET))?;
+        self.sink.write_u32::<LittleEndian>(data_ptr)?;
+        self.sink.seek(SeekFrom::Start(NUM_CHANNELS_OFFSET))?;
+        self.sink
+            .write_u32::<LittleEndian>(self.header.num_channels)?;
+
         Ok(())
     }
 
-    fn write_channel_metadata(&mut self, addr: u32, channel: &ChannelMetadata) -> I2Result<()> {
+    /// Writes the metadata entry of `channel`, `raw_range` are the max and min raw sample values
+    /// of the channel, see [raw_range]
+    fn write_channel_metadata(
+        &mut self,
+        addr: u32,
+        channel: &ChannelMetadata,
+        raw_range: Option<(i32, i32)>,
+    ) -> I2Result<()> {
         self.sink.seek(SeekFrom::Start(addr as u64))?;
 
         self.sink.write_u32::<LittleEndian>(channel.prev_addr)?;
@@ -182,28 +676,39 @@ impl<'a, S: Write + Seek> LDWriter<'a, S> {
 
         self.sink.write_u16::<LittleEndian>(channel.sample_rate)?;
 
-        self.sink.write_u16::<LittleEndian>(channel.offset)?;
-        self.sink.write_u16::<LittleEndian>(channel.mul)?;
-        self.sink.write_u16::<LittleEndian>(channel.scale)?;
+        self.sink.write_i16::<LittleEndian>(channel.offset)?;
+        self.sink.write_i16::<LittleEndian>(channel.mul)?;
+        self.sink.write_i16::<LittleEndian>(channel.scale)?;
         self.sink.write_i16::<LittleEndian>(channel.dec_places)?;
 
         self.write_string(32, &channel.name)?;
         self.write_string(8, &channel.short_name)?;
         self.write_string(12, &channel.unit)?;
 
-        // TODO: Not sure what this is...
-        self.sink.write_u8(201)?;
-        self.sink.write(&[0u8; 39])?;
+        self.write_raw_range(raw_range)?;
+        self.sink.write_all(&[0u8; 32])?;
+        Ok(())
+    }
+
+    /// Writes the max and min raw sample values at the start of the metadata trailer
+    ///
+    /// These match the channel data of every channel in Sample1.ld. We haven't found display
+    /// ranges or alarm thresholds anywhere in the trailer, the rest of it is always zeros.
+    fn write_raw_range(&mut self, raw_range: Option<(i32, i32)>) -> I2Result<()> {
+        let (max, min) = raw_range.unwrap_or((0, 0));
+        self.sink.write_i32::<LittleEndian>(max)?;
+        self.sink.write_i32::<LittleEndian>(min)?;
         Ok(())
     }
 
-    fn write_samples(&mut self, addr: u32, sample: &Vec<Sample>) -> I2Result<()> {
+    fn write_samples(&mut self, addr: u32, sample: &[Sample]) -> I2Result<()> {
         self.sink.seek(SeekFrom::Start(addr as u64))?;
 
         for s in sample {
             match s {
                 Sample::I16(i) => self.sink.write_i16::<LittleEndian>(*i)?,
                 Sample::I32(i) => self.sink.write_i32::<LittleEndian>(*i)?,
+                Sample::F16(f) => self.sink.write_u16::<LittleEndian>(f32_to_f16(*f))?,
                 Sample::F32(f) => self.sink.write_f32::<LittleEndian>(*f)?,
             }
         }
@@ -214,18 +719,274 @@ impl<'a, S: Write + Seek> LDWriter<'a, S> {
     /// Writes a string in a field up to `max_len`
     ///
     /// The I2 format (as far as we understand) stores strings as utf8 bytes with 0 bytes for padding
+    ///
+    /// Strings that are too long are truncated on a char boundary, so that they stay valid utf8.
     pub(crate) fn write_string(&mut self, max_len: usize, string: &str) -> I2Result<()> {
-        let bytes: Vec<u8> = string.bytes().take(max_len).collect();
-        self.sink.write(&bytes[..])?;
-        let zeros: Vec<u8> = iter::repeat(0).take(max_len - bytes.len()).collect();
-        self.sink.write(&zeros[..])?;
+        let len = (0..=max_len.min(string.len()))
+            .rev()
+            .find(|i| string.is_char_boundary(*i))
+            .unwrap_or(0);
+        let bytes = &string.as_bytes()[..len];
+        self.sink.write_all(bytes)?;
+        let zeros: Vec<u8> = iter::repeat_n(0, max_len - bytes.len()).collect();
+        self.sink.write_all(&zeros[..])?;
+        Ok(())
+    }
+}
+
+/// Picks the smallest integer datatype and number of decimal places that represents all `values`
+fn pick_datatype(values: &[f64]) -> (Datatype, i16) {
+    const MAX_DEC_PLACES: i16 = 6;
+
+    let dec_places = (0..=MAX_DEC_PLACES).find(|d| {
+        let factor = 10.0f64.powi(*d as i32);
+        // Relative to the value, so that tiny values aren't rounded away to 0
+        values.iter().all(|v| {
+            let raw = v * factor;
+            raw.is_finite() && (raw.round() / factor - v).abs() <= v.abs() * 1e-9
+        })
+    });
+
+    let Some(dec_places) = dec_places else {
+        return (Datatype::F32, 0);
+    };
+
+    let factor = 10.0f64.powi(dec_places as i32);
+    let fits = |min: f64, max: f64| {
+        values
+            .iter()
+            .all(|v| (v * factor).round() >= min && (v * factor).round() <= max)
+    };
+
+    if fits(i16::MIN as f64, i16::MAX as f64) {
+        (Datatype::I16, dec_places)
+    } else if fits(i32::MIN as f64, i32::MAX as f64) {
+        (Datatype::I32, dec_places)
+    } else {
+        (Datatype::F32, 0)
+    }
+}
+
+/// Checks that all `samples` can be stored in the datatype of `channel`
+fn check_sample_datatypes(channel: &ChannelMetadata, samples: &[Sample]) -> I2Result<()> {
+    for sample in samples {
+        let found = match sample {
+            Sample::I16(_) => Datatype::I16,
+            Sample::I32(_) => Datatype::I32,
+            Sample::F16(_) => Datatype::F16,
+            Sample::F32(_) => Datatype::F32,
+        };
+        let matches = matches!(
+            (&channel.datatype, sample),
+            (Datatype::Beacon16 | Datatype::I16, Sample::I16(_))
+                | (Datatype::Beacon32 | Datatype::I32, Sample::I32(_))
+                | (Datatype::F16, Sample::F16(_))
+                | (Datatype::F32, Sample::F32(_))
+        );
+        if !matches {
+            return Err(I2Error::SampleDatatypeMismatch {
+                channel: channel.name.clone(),
+                expected: channel.datatype.clone(),
+                found,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Returns the max and min raw values of integer samples
+///
+/// We don't know what is stored for float channels, so those are left as `None`
+fn raw_range(samples: &[Sample]) -> Option<(i32, i32)> {
+    samples
+        .iter()
+        .map(|s| match s {
+            Sample::I16(v) => Some(*v as i32),
+            Sample::I32(v) => Some(*v),
+            Sample::F16(_) | Sample::F32(_) => None,
+        })
+        .try_fold(None, |range, value| {
+            let value = value?;
+            Some(Some(match range {
+                Some((max, min)) => (value.max(max), value.min(min)),
+                None => (value, value),
+            }))
+        })
+        .flatten()
+}
+
+/// A channel that is being written in blocks, see [LDWriter::begin_channel]
+#[derive(Debug)]
+pub struct ChannelHandle<'w, 'a, S: Write + Seek> {
+    writer: &'w mut LDWriter<'a, S>,
+    meta_addr: u32,
+    channel: ChannelMetadata,
+    /// Max and min raw values of the samples written so far, see [raw_range]
+    raw_range: Option<(i32, i32)>,
+}
+
+impl<'w, 'a, S: Write + Seek> ChannelHandle<'w, 'a, S> {
+    /// Appends a block of samples to the end of this channel's data
+    ///
+    /// Fails with [I2Error::SampleDatatypeMismatch] if the samples don't match the channel's
+    /// datatype.
+    pub fn write_block(&mut self, samples: &[Sample]) -> I2Result<()> {
+        check_sample_datatypes(&self.channel, samples)?;
+        let addr = self.channel.data_addr + self.channel.data_size();
+        self.writer.write_samples(addr, samples)?;
+        self.channel.data_count += samples.len() as u32;
+
+        self.raw_range = merge_raw_ranges(self.raw_range, raw_range(samples));
+        Ok(())
+    }
+
+    /// Encodes physical values into samples and appends them to this channel's data
+    ///
+    /// See [Sample::encode_f64_checked] and [LDWriter::set_overflow_policy]
+    pub fn write_block_f64(&mut self, values: &[f64]) -> I2Result<()> {
+        let samples = values
+            .iter()
+            .map(|v| Sample::encode_f64_checked(*v, &self.channel, self.writer.overflow_policy))
+            .collect::<I2Result<Vec<_>>>()?;
+        self.write_block(&samples)
+    }
+
+    /// Patches the final sample count and raw range into the channel metadata and the header
+    pub fn finish(self) -> I2Result<()> {
+        let writer = self.writer;
+        writer.finish_channel(self.meta_addr, &self.channel, self.raw_range)?;
+        writer.end_addr = self.channel.data_addr + self.channel.data_size();
+        writer.write_extensions(writer.end_addr)?;
+        writer.pad()
+    }
+}
+
+/// Channels that are written as their samples arrive, see [LDWriter::begin_stream]
+#[derive(Debug)]
+pub struct StreamWriter<'w, 'a, S: Write + Seek> {
+    writer: &'w mut LDWriter<'a, S>,
+    max_duration_secs: u32,
+    channels: Vec<StreamChannel>,
+}
+
+/// A channel registered with [StreamWriter::register_channel]
+#[derive(Debug)]
+struct StreamChannel {
+    meta_addr: u32,
+    channel: ChannelMetadata,
+    /// Number of samples that fit in the space reserved for this channel
+    capacity: u32,
+    /// Max and min raw values of the samples written so far, see [raw_range]
+    raw_range: Option<(i32, i32)>,
+}
+
+impl<'w, 'a, S: Write + Seek> StreamWriter<'w, 'a, S> {
+    /// Adds a channel to the file, returning the id to push its samples with
+    ///
+    /// The channel's metadata entry is written right away, followed by the space reserved for
+    /// its data. Channels are usually all registered before the first samples arrive, but they
+    /// can also be added later on.
+    ///
+    /// Fails with [I2Error::StreamAddressOverflow] if the reserved space would go past the
+    /// largest address of an ld file.
+    pub fn register_channel(&mut self, mut channel: ChannelMetadata) -> I2Result<usize> {
+        // Addresses in the file are u32, so the reserved space has to end below 4 GiB
+        let capacity = (channel.sample_rate as u32).checked_mul(self.max_duration_secs);
+        let reserved_end = capacity
+            .and_then(|c| c.checked_mul(channel.datatype.size() as u32))
+            .and_then(|size| size.checked_add(ChannelMetadata::ENTRY_SIZE))
+            .and_then(|size| size.checked_add(self.writer.end_addr));
+        let (Some(capacity), Some(reserved_end)) = (capacity, reserved_end) else {
+            return Err(I2Error::StreamAddressOverflow {
+                channel: channel.name,
+                max_duration_secs: self.max_duration_secs,
+            });
+        };
+
+        let meta_addr = self.writer.link_channel(&mut channel)?;
+        self.writer.end_addr = reserved_end;
+
+        self.channels.push(StreamChannel {
+            meta_addr,
+            channel,
+            capacity,
+            raw_range: None,
+        });
+        Ok(self.channels.len() - 1)
+    }
+
+    /// Appends `samples` to the end of the data of channel `id`
+    ///
+    /// Fails with [I2Error::StreamCapacityExceeded] if the samples don't fit in the space
+    /// reserved for the channel, in which case none of them are written.
+    pub fn push(&mut self, id: usize, samples: &[Sample]) -> I2Result<()> {
+        let stream = self
+            .channels
+            .get_mut(id)
+            .ok_or(I2Error::UnknownStreamChannel { id })?;
+        check_sample_datatypes(&stream.channel, samples)?;
+        if stream.channel.data_count as usize + samples.len() > stream.capacity as usize {
+            return Err(I2Error::StreamCapacityExceeded {
+                channel: stream.channel.name.clone(),
+                capacity: stream.capacity,
+            });
+        }
+
+        let addr = stream.channel.data_addr + stream.channel.data_size();
+        self.writer.write_samples(addr, samples)?;
+        stream.channel.data_count += samples.len() as u32;
+        stream.raw_range = merge_raw_ranges(stream.raw_range, raw_range(samples));
         Ok(())
     }
+
+    /// Encodes physical values into samples and appends them to the data of channel `id`
+    ///
+    /// See [StreamWriter::push] and [Sample::encode_f64_checked]
+    pub fn push_f64(&mut self, id: usize, values: &[f64]) -> I2Result<()> {
+        let channel = &self
+            .channels
+            .get(id)
+            .ok_or(I2Error::UnknownStreamChannel { id })?
+            .channel;
+        let samples = values
+            .iter()
+            .map(|v| Sample::encode_f64_checked(*v, channel, self.writer.overflow_policy))
+            .collect::<I2Result<Vec<_>>>()?;
+        self.push(id, &samples)
+    }
+
+    /// Patches the sample counts of every channel and the number of channels in the header
+    ///
+    /// Space reserved for samples that never arrived is left in the file, except after the
+    /// last channel, where the extension block and any later channels are written.
+    pub fn finalize(self) -> I2Result<()> {
+        let writer = self.writer;
+        for stream in self.channels.iter() {
+            writer.finish_channel(stream.meta_addr, &stream.channel, stream.raw_range)?;
+        }
+        if let Some(last) = self.channels.last() {
+            writer.end_addr = last.channel.data_addr + last.channel.data_size();
+        }
+        writer.write_extensions(writer.end_addr)?;
+        writer.pad()
+    }
+}
+
+/// Combines the raw ranges of two sets of samples, see [raw_range]
+fn merge_raw_ranges(a: Option<(i32, i32)>, b: Option<(i32, i32)>) -> Option<(i32, i32)> {
+    match (a, b) {
+        (Some((max_a, min_a)), Some((max_b, min_b))) => Some((max_a.max(max_b), min_a.min(min_b))),
+        (a, b) => a.or(b),
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{ChannelMetadata, Datatype, Header, LDWriter, Sample};
+    use crate::{
+        ChannelMetadata, Datatype, Event, Header, I2Error, LDReader, LDWriter, OverflowPolicy,
+        Sample, Vehicle, Venue, EXTENSION_MARKER,
+    };
+    use std::fs;
     use std::io::Cursor;
     use std::iter;
 
@@ -237,6 +998,7 @@ mod tests {
             device_serial: 12007,
             device_type: "ADL".to_string(),
             device_version: 420,
+            device_flags: 0x0080,
             num_channels: 1,
             date_string: "23/11/2005".to_string(),
             time_string: "09:53:00".to_string(),
@@ -248,36 +1010,31 @@ mod tests {
         }
     }
 
-    #[test]
-    fn test_write_string() {
-        let bytes: Vec<u8> = iter::repeat(1u8).take(8).collect();
-        let mut cursor = Cursor::new(bytes);
-        let mut writer = LDWriter::new(&mut cursor, sample_header());
-
-        writer.write_string(8, "OK").unwrap();
-
-        let bytes = cursor.into_inner();
-        assert_eq!(bytes, [79, 75, 0, 0, 0, 0, 0, 0]);
-    }
-
-    #[test]
-    fn test_write_string_max_len() {
-        let bytes: Vec<u8> = iter::repeat(1u8).take(8).collect();
-        let mut cursor = Cursor::new(bytes);
-        let mut writer = LDWriter::new(&mut cursor, sample_header());
-
-        writer.write_string(8, "test123456").unwrap();
-
-        let bytes = cursor.into_inner();
-        assert_eq!(bytes, [116, 101, 115, 116, 49, 50, 51, 52]);
+    fn sample_channel(name: &str) -> ChannelMetadata {
+        ChannelMetadata {
+            prev_addr: 0,
+            next_addr: 0,
+            data_addr: 0,
+            data_count: 0,
+            datatype: Datatype::I16,
+            sample_rate: 10,
+            offset: 0,
+            mul: 1,
+            scale: 1,
+            dec_places: 0,
+            name: name.to_string(),
+            short_name: name.to_string(),
+            unit: "".to_string(),
+        }
     }
 
     #[test]
-    fn test_write_single_channel() {
-        let total_size = 13384 + 132; // header + 1 channel + samples
-        let bytes: Vec<u8> = iter::repeat(0u8).take(total_size).collect();
-        let mut cursor = Cursor::new(bytes);
-
+    fn write_built_header() {
+        let header = Header::builder()
+            .device_type("ADL")
+            .venue("Calder")
+            .date("23/11/2005")
+            .build();
         let channel = ChannelMetadata {
             prev_addr: 0,
             next_addr: 0,
@@ -294,33 +1051,278 @@ mod tests {
             unit: "C".to_string(),
         };
 
-        let samples = vec![
-            Sample::I16(0),
-            Sample::I16(1),
-            Sample::I16(2),
-            Sample::I16(3),
-        ];
+        let mut cursor = Cursor::new(Vec::new());
+        LDWriter::new(&mut cursor, header.clone())
+            .with_channel(channel, vec![Sample::I16(1), Sample::I16(2)])
+            .write()
+            .unwrap();
+
+        let mut reader = LDReader::new(&mut cursor);
+        assert_eq!(
+            reader.read_header().unwrap(),
+            Header {
+                channel_meta_ptr: 0x3448,
+                channel_data_ptr: 0x3448 + 124,
+                num_channels: 1,
+                ..header
+            }
+        );
+        let channels = reader.read_channels().unwrap();
+        assert_eq!(channels.len(), 1);
+        assert_eq!(
+            reader.channel_data(&channels[0]).unwrap(),
+            [Sample::I16(1), Sample::I16(2)]
+        );
+    }
+
+    #[test]
+    fn write_event() {
+        let event = Event {
+            name: "Endurance".to_string(),
+            session: "Race".to_string(),
+            comment: "Second stint".to_string(),
+            venue_addr: 0,
+        };
 
+        let mut cursor = Cursor::new(Vec::new());
         LDWriter::new(&mut cursor, sample_header())
-            .with_channel(channel, samples)
+            .with_event(event.clone())
             .write()
             .unwrap();
 
-        const EXPECTED: [u8; 132] = [
-            0x00, 0x00, 0x00, 0x00, // prev_addr
-            0x00, 0x00, 0x00, 0x00, // next_addr
-            0xC4, 0x34, 0x00, 0x00, // data_addr
-            0x04, 0x00, 0x00, 0x00, // samples
-            // Channel
-            0x04, 0x00, 0x03, 0x00, 0x02, 0x00, 0x02, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00,
-            0x01, 0x00, 0x41, 0x69, 0x72, 0x20, 0x54, 0x65, 0x6D, 0x70, 0x20, 0x49, 0x6E, 0x6C,
-            0x65, 0x74, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x41, 0x69, 0x72, 0x20, 0x54, 0x65, 0x6D, 0x00,
-            0x43, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xC9, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // Channel end
-            // Samples
+        let mut reader = LDReader::new(&mut cursor);
+        assert_eq!(reader.read_header().unwrap().event_ptr, 0x6E2);
+        assert_eq!(reader.read_event().unwrap(), Some(event));
+        assert_eq!(reader.read_venue().unwrap(), None);
+    }
+
+    #[test]
+    fn write_venue() {
+        let event = Event {
+            name: "Endurance".to_string(),
+            session: "Race".to_string(),
+            comment: "".to_string(),
+            venue_addr: 0,
+        };
+        let venue = Venue {
+            name: "Calder Park".to_string(),
+            raw_extra: vec![0u8; 1034],
+            vehicle_addr: 0,
+        };
+
+        let mut cursor = Cursor::new(Vec::new());
+        LDWriter::new(&mut cursor, sample_header())
+            .with_event(event)
+            .with_venue(venue.clone())
+            .write()
+            .unwrap();
+
+        let mut reader = LDReader::new(&mut cursor);
+        assert_eq!(reader.read_event().unwrap().unwrap().venue_addr, 0x1336);
+        assert_eq!(reader.read_venue().unwrap(), Some(venue));
+    }
+
+    #[test]
+    fn write_metadata_chain() {
+        let event = Event {
+            name: "Endurance".to_string(),
+            session: "Race".to_string(),
+            comment: "Night stint".to_string(),
+            venue_addr: 0,
+        };
+        let venue = Venue {
+            name: "Calder Park".to_string(),
+            raw_extra: vec![0u8; 1034],
+            vehicle_addr: 0,
+        };
+        let vehicle = Vehicle {
+            id: "22B".to_string(),
+            weight: 1120,
+            _type: "Sports Car".to_string(),
+            comment: "Spare chassis".to_string(),
+        };
+
+        let mut cursor = Cursor::new(Vec::new());
+        LDWriter::new(&mut cursor, sample_header())
+            .with_event(event.clone())
+            .with_venue(venue.clone())
+            .with_vehicle(vehicle.clone())
+            .write()
+            .unwrap();
+
+        let mut reader = LDReader::new(&mut cursor);
+        assert_eq!(
+            reader.read_header().unwrap(),
+            Header {
+                channel_meta_ptr: 0,
+                channel_data_ptr: 0,
+                event_ptr: 0x6E2,
+                num_channels: 0,
+                ..sample_header()
+            }
+        );
+        assert_eq!(
+            reader.read_event().unwrap(),
+            Some(Event {
+                venue_addr: 0x1336,
+                ..event
+            })
+        );
+        assert_eq!(
+            reader.read_venue().unwrap(),
+            Some(Venue {
+                vehicle_addr: 0x1F54,
+                ..venue
+            })
+        );
+        assert_eq!(reader.read_vehicle().unwrap(), Some(vehicle));
+    }
+
+    #[test]
+    fn write_venue_without_vehicle() {
+        let event = Event {
+            name: "Endurance".to_string(),
+            session: "Race".to_string(),
+            comment: "".to_string(),
+            venue_addr: 0,
+        };
+        let venue = Venue {
+            name: "Calder Park".to_string(),
+            raw_extra: vec![0u8; 1034],
+            vehicle_addr: 0x1F54,
+        };
+
+        let mut cursor = Cursor::new(Vec::new());
+        LDWriter::new(&mut cursor, sample_header())
+            .with_event(event)
+            .with_venue(venue)
+            .write()
+            .unwrap();
+
+        let mut reader = LDReader::new(&mut cursor);
+        assert_eq!(reader.read_venue().unwrap().unwrap().vehicle_addr, 0);
+        assert_eq!(reader.read_vehicle().unwrap(), None);
+    }
+
+    #[test]
+    fn write_event_without_venue() {
+        // Sample1.ld has a venue at this address, which is also present in our header template
+        let event = Event {
+            name: "Endurance".to_string(),
+            session: "Race".to_string(),
+            comment: "".to_string(),
+            venue_addr: 0x1336,
+        };
+
+        let mut cursor = Cursor::new(Vec::new());
+        LDWriter::new(&mut cursor, sample_header())
+            .with_event(event)
+            .write()
+            .unwrap();
+
+        let mut reader = LDReader::new(&mut cursor);
+        assert_eq!(reader.read_event().unwrap().unwrap().venue_addr, 0);
+        assert_eq!(reader.read_venue().unwrap(), None);
+    }
+
+    #[test]
+    fn test_write_string() {
+        let bytes: Vec<u8> = iter::repeat_n(1u8, 8).collect();
+        let mut cursor = Cursor::new(bytes);
+        let mut writer = LDWriter::new(&mut cursor, sample_header());
+
+        writer.write_string(8, "OK").unwrap();
+
+        let bytes = cursor.into_inner();
+        assert_eq!(bytes, [79, 75, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_write_string_max_len() {
+        let bytes: Vec<u8> = iter::repeat_n(1u8, 8).collect();
+        let mut cursor = Cursor::new(bytes);
+        let mut writer = LDWriter::new(&mut cursor, sample_header());
+
+        writer.write_string(8, "test123456").unwrap();
+
+        let bytes = cursor.into_inner();
+        assert_eq!(bytes, [116, 101, 115, 116, 49, 50, 51, 52]);
+    }
+
+    #[test]
+    fn test_write_string_multibyte_boundary() {
+        let mut cursor = Cursor::new(Vec::new());
+        let mut writer = LDWriter::new(&mut cursor, sample_header());
+
+        // The 'é' takes bytes 4 and 5, so it doesn't fit
+        writer.write_string(4, "Café").unwrap();
+
+        let bytes = cursor.into_inner();
+        assert_eq!(bytes, [b'C', b'a', b'f', 0]);
+        assert_eq!(std::str::from_utf8(&bytes[..3]).unwrap(), "Caf");
+
+        // 'ü' takes 2 bytes, so the last one is split by the end of the 64 byte venue field
+        let long_venue = format!("a{}", "ü".repeat(40));
+        let header = Header {
+            venue: long_venue.clone(),
+            ..sample_header()
+        };
+        let mut cursor = Cursor::new(Vec::new());
+        LDWriter::new(&mut cursor, header).write().unwrap();
+        let venue = LDReader::new(&mut cursor).read_header().unwrap().venue;
+        assert_eq!(venue.len(), 63);
+        assert!(long_venue.starts_with(&venue));
+    }
+
+    #[test]
+    fn test_write_single_channel() {
+        let total_size = 13384 + 132; // header + 1 channel + samples
+        let bytes: Vec<u8> = iter::repeat_n(0u8, total_size).collect();
+        let mut cursor = Cursor::new(bytes);
+
+        let channel = ChannelMetadata {
+            prev_addr: 0,
+            next_addr: 0,
+            data_addr: 0,
+            data_count: 0,
+            datatype: Datatype::I16,
+            sample_rate: 2,
+            offset: 0,
+            mul: 1,
+            scale: 1,
+            dec_places: 1,
+            name: "Air Temp Inlet".to_string(),
+            short_name: "Air Tem".to_string(),
+            unit: "C".to_string(),
+        };
+
+        let samples = vec![
+            Sample::I16(0),
+            Sample::I16(1),
+            Sample::I16(2),
+            Sample::I16(3),
+        ];
+
+        LDWriter::new(&mut cursor, sample_header())
+            .with_channel(channel, samples)
+            .write()
+            .unwrap();
+
+        const EXPECTED: [u8; 132] = [
+            0x00, 0x00, 0x00, 0x00, // prev_addr
+            0x00, 0x00, 0x00, 0x00, // next_addr
+            0xC4, 0x34, 0x00, 0x00, // data_addr
+            0x04, 0x00, 0x00, 0x00, // samples
+            // Channel
+            0x04, 0x00, 0x03, 0x00, 0x02, 0x00, 0x02, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00,
+            0x01, 0x00, 0x41, 0x69, 0x72, 0x20, 0x54, 0x65, 0x6D, 0x70, 0x20, 0x49, 0x6E, 0x6C,
+            0x65, 0x74, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x41, 0x69, 0x72, 0x20, 0x54, 0x65, 0x6D, 0x00,
+            0x43, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // Channel end
+            // Samples
             0x00, 0x00, // Sample 1
             0x01, 0x00, // Sample 2
             0x02, 0x00, // Sample 3
@@ -335,7 +1337,7 @@ mod tests {
     #[test]
     fn test_write_multi_channel() {
         let total_size = 13384 + 132 + 140; // header + 2 channel + samples
-        let bytes: Vec<u8> = iter::repeat(0u8).take(total_size).collect();
+        let bytes: Vec<u8> = iter::repeat_n(0u8, total_size).collect();
         let mut cursor = Cursor::new(bytes);
 
         let channel0 = ChannelMetadata {
@@ -399,8 +1401,8 @@ mod tests {
             0x01, 0x00, 0x41, 0x69, 0x72, 0x20, 0x54, 0x65, 0x6D, 0x70, 0x20, 0x49, 0x6E, 0x6C,
             0x65, 0x74, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
             0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x41, 0x69, 0x72, 0x20, 0x54, 0x65, 0x6D, 0x00,
-            0x43, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xC9, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x43, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x90, 0x01,
+            0x00, 0x00, 0xBE, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
             0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
             0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // Channel end
             // Channel 2
@@ -423,7 +1425,7 @@ mod tests {
             0x45, 0x6E, 0x67, 0x54, 0x65, 0x6D, 0x70, 0x00, // short name
             0x43, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // unit
             // Rest
-            0xC9, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x8C, 0x64, 0x1E, 0x17, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
             0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
             0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
             0x00, // Channel end
@@ -441,4 +1443,949 @@ mod tests {
         let channel_data = cursor.into_inner();
         assert_eq!(channel_data[13384..], EXPECTED);
     }
+
+    #[test]
+    fn test_write_channel_blocks() {
+        let mut cursor = Cursor::new(Vec::new());
+
+        let channel = ChannelMetadata {
+            prev_addr: 0,
+            next_addr: 0,
+            data_addr: 0,
+            data_count: 0,
+            datatype: Datatype::I16,
+            sample_rate: 2,
+            offset: 0,
+            mul: 1,
+            scale: 1,
+            dec_places: 1,
+            name: "Air Temp Inlet".to_string(),
+            short_name: "Air Tem".to_string(),
+            unit: "C".to_string(),
+        };
+
+        let mut writer = LDWriter::new(&mut cursor, sample_header());
+        let mut handle = writer.begin_channel(channel.clone()).unwrap();
+        handle
+            .write_block(&[Sample::I16(0), Sample::I16(1)])
+            .unwrap();
+        handle.write_block(&[Sample::I16(2)]).unwrap();
+        handle
+            .write_block(&[Sample::I16(3), Sample::I16(4), Sample::I16(5)])
+            .unwrap();
+        handle.finish().unwrap();
+
+        let mut reader = LDReader::new(&mut cursor);
+        let header = reader.read_header().unwrap();
+        assert_eq!(header.num_channels, 1);
+
+        let channels = reader.read_channels().unwrap();
+        assert_eq!(channels.len(), 1);
+        assert_eq!(channels[0].name, channel.name);
+        assert_eq!(channels[0].data_count, 6);
+
+        let data = reader.channel_data(&channels[0]).unwrap();
+        assert_eq!(data, (0..6).map(Sample::I16).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_stream_packets() {
+        let mut cursor = Cursor::new(Vec::new());
+        let mut writer = LDWriter::new(&mut cursor, sample_header());
+        let mut stream = writer.begin_stream(10).unwrap();
+        let rpm = stream
+            .register_channel(ChannelMetadata {
+                sample_rate: 20,
+                ..sample_channel("RPM")
+            })
+            .unwrap();
+        let speed = stream
+            .register_channel(ChannelMetadata {
+                datatype: Datatype::F32,
+                ..sample_channel("Speed")
+            })
+            .unwrap();
+
+        // Each packet carries 2 RPM samples and 1 speed sample
+        for packet in 0..15 {
+            let rpm_samples = [Sample::I16(packet * 100), Sample::I16(packet * 100 + 50)];
+            stream.push(rpm, &rpm_samples).unwrap();
+            stream.push_f64(speed, &[packet as f64 * 1.5]).unwrap();
+        }
+        stream.finalize().unwrap();
+
+        let mut reader = LDReader::new(&mut cursor);
+        let header = reader.read_header().unwrap();
+        assert_eq!(header.num_channels, 2);
+
+        let channels = reader.read_channels().unwrap();
+        assert_eq!(channels.len(), 2);
+        assert_eq!(channels[0].name, "RPM");
+        assert_eq!(channels[0].data_count, 30);
+        assert_eq!(channels[1].name, "Speed");
+        assert_eq!(channels[1].data_count, 15);
+
+        let rpm = reader.channel_data_decoded(&channels[0]).unwrap();
+        assert_eq!(rpm[..4], [0.0, 50.0, 100.0, 150.0]);
+        assert_eq!(rpm[29], 1450.0);
+        let speed = reader.channel_data_decoded(&channels[1]).unwrap();
+        assert_eq!(speed, (0..15).map(|p| p as f64 * 1.5).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_stream_interleaved() {
+        let mut cursor = Cursor::new(Vec::new());
+        let mut writer = LDWriter::new(&mut cursor, sample_header());
+        let mut stream = writer.begin_stream(60).unwrap();
+        let ids: Vec<usize> = [
+            ChannelMetadata {
+                sample_rate: 50,
+                ..sample_channel("Throttle")
+            },
+            ChannelMetadata {
+                datatype: Datatype::I32,
+                sample_rate: 50,
+                ..sample_channel("Brake")
+            },
+            ChannelMetadata {
+                datatype: Datatype::F32,
+                sample_rate: 20,
+                ..sample_channel("Lat G")
+            },
+        ]
+        .into_iter()
+        .map(|c| stream.register_channel(c).unwrap())
+        .collect();
+
+        // Bursts of different sizes, in an order that changes every round
+        let mut expected = vec![Vec::new(); 3];
+        for round in 0..20i32 {
+            for step in 0..3 {
+                let id = ids[(round as usize + step) % 3];
+                let samples: Vec<Sample> = (0..(round % 4 + step as i32))
+                    .map(|i| match id {
+                        0 => Sample::I16((round * 10 + i) as i16),
+                        1 => Sample::I32(-(round * 10 + i)),
+                        _ => Sample::F32(round as f32 + i as f32 / 4.0),
+                    })
+                    .collect();
+                stream.push(id, &samples).unwrap();
+                expected[id].extend(samples);
+            }
+        }
+        stream.finalize().unwrap();
+
+        // Block channels can still be added after the stream
+        writer.log("Gear", "", 10, &[1.0, 2.0, 3.0]).unwrap();
+        let layout = writer.layout().clone();
+
+        let mut reader = LDReader::new(&mut cursor);
+        assert_eq!(reader.read_header().unwrap().num_channels, 4);
+
+        let channels = reader.read_channels().unwrap();
+        let names: Vec<_> = channels.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["Throttle", "Brake", "Lat G", "Gear"]);
+        for (channel, expected) in channels.iter().zip(&expected) {
+            assert_eq!(&reader.channel_data(channel).unwrap(), expected);
+        }
+        assert_eq!(
+            reader.channel_data_decoded(&channels[3]).unwrap(),
+            [1.0, 2.0, 3.0]
+        );
+
+        for (channel, layout) in channels.iter().zip(&layout.channels) {
+            assert_eq!(channel.data_addr, layout.data_addr);
+            assert_eq!(channel.data_size(), layout.data_size);
+        }
+    }
+
+    #[test]
+    fn test_stream_capacity() {
+        let mut cursor = Cursor::new(Vec::new());
+        let mut writer = LDWriter::new(&mut cursor, sample_header());
+        let mut stream = writer.begin_stream(1).unwrap();
+        let gear = stream
+            .register_channel(ChannelMetadata {
+                sample_rate: 2,
+                ..sample_channel("Gear")
+            })
+            .unwrap();
+
+        stream.push(gear, &[Sample::I16(1)]).unwrap();
+        match stream.push(gear, &[Sample::I16(2), Sample::I16(3)]) {
+            Err(I2Error::StreamCapacityExceeded { channel, capacity }) => {
+                assert_eq!(channel, "Gear");
+                assert_eq!(capacity, 2);
+            }
+            other => panic!("expected a StreamCapacityExceeded error, got {:?}", other),
+        }
+        assert!(matches!(
+            stream.push(gear + 1, &[Sample::I16(2)]),
+            Err(I2Error::UnknownStreamChannel { id: 1 })
+        ));
+        stream.push(gear, &[Sample::I16(2)]).unwrap();
+
+        let mut huge = sample_channel("Huge");
+        stream.finalize().unwrap();
+
+        // Overflows of the sample count, the size in bytes, and the end address of the reserved
+        // space, none of which link the channel into the file
+        for (max_duration_secs, sample_rate, datatype) in [
+            (u32::MAX, u16::MAX, Datatype::I16),
+            (u32::MAX, 1, Datatype::I32),
+            (i32::MAX as u32, 1, Datatype::I16),
+        ] {
+            huge.sample_rate = sample_rate;
+            huge.datatype = datatype;
+            let mut stream = writer.begin_stream(max_duration_secs).unwrap();
+            match stream.register_channel(huge.clone()) {
+                Err(I2Error::StreamAddressOverflow {
+                    channel,
+                    max_duration_secs: secs,
+                }) => {
+                    assert_eq!(channel, "Huge");
+                    assert_eq!(secs, max_duration_secs);
+                }
+                other => panic!("expected a StreamAddressOverflow error, got {:?}", other),
+            }
+            stream.finalize().unwrap();
+        }
+
+        let mut reader = LDReader::new(&mut cursor);
+        let channels = reader.read_channels().unwrap();
+        assert_eq!(channels.len(), 1);
+        assert_eq!(
+            reader.channel_data(&channels[0]).unwrap(),
+            [Sample::I16(1), Sample::I16(2)]
+        );
+    }
+
+    fn write_overflowing_value(policy: OverflowPolicy) -> Result<Sample, I2Error> {
+        let mut cursor = Cursor::new(Vec::new());
+
+        let channel = ChannelMetadata {
+            prev_addr: 0,
+            next_addr: 0,
+            data_addr: 0,
+            data_count: 0,
+            datatype: Datatype::I16,
+            sample_rate: 1,
+            offset: 0,
+            mul: 1,
+            scale: 1,
+            dec_places: 0,
+            name: "Engine RPM".to_string(),
+            short_name: "RPM".to_string(),
+            unit: "rpm".to_string(),
+        };
+
+        let mut writer = LDWriter::new(&mut cursor, sample_header());
+        writer.set_overflow_policy(policy);
+        let mut handle = writer.begin_channel(channel).unwrap();
+        handle.write_block_f64(&[40000.0])?;
+        handle.finish().unwrap();
+
+        let mut reader = LDReader::new(&mut cursor);
+        let channels = reader.read_channels().unwrap();
+        Ok(reader.channel_data(&channels[0]).unwrap()[0].clone())
+    }
+
+    #[test]
+    fn test_overflow_policy_error() {
+        let err = write_overflowing_value(OverflowPolicy::Error).unwrap_err();
+        assert!(matches!(
+            err,
+            I2Error::SampleOverflow {
+                datatype: Datatype::I16,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_overflow_policy_saturate() {
+        let sample = write_overflowing_value(OverflowPolicy::Saturate).unwrap();
+        assert_eq!(sample, Sample::I16(i16::MAX));
+    }
+
+    #[test]
+    fn test_overflow_policy_wrap() {
+        let sample = write_overflowing_value(OverflowPolicy::Wrap).unwrap();
+        assert_eq!(sample, Sample::I16(40000u16 as i16));
+    }
+
+    #[test]
+    fn test_log_values() {
+        let mut cursor = Cursor::new(Vec::new());
+
+        let small = [1.5, 2.25, -3.75, 10.0];
+        let large = [1.25, 2.5, -3.75, 100.125];
+        let irrational = [0.1, std::f64::consts::PI];
+
+        let mut writer = LDWriter::new(&mut cursor, sample_header());
+        writer.log("Small", "V", 10, &small).unwrap();
+        writer.log("Large", "V", 10, &large).unwrap();
+        writer.log("Irrational", "V", 10, &irrational).unwrap();
+
+        let mut reader = LDReader::new(&mut cursor);
+        let channels = reader.read_channels().unwrap();
+        assert_eq!(channels[0].datatype, Datatype::I16);
+        assert_eq!(channels[0].dec_places, 2);
+        assert_eq!(channels[1].datatype, Datatype::I32);
+        assert_eq!(channels[1].dec_places, 3);
+        assert_eq!(channels[2].datatype, Datatype::F32);
+
+        for (channel, expected) in channels.iter().zip([&small[..], &large, &irrational]) {
+            let values = reader.decode_channel(channel).unwrap().values;
+            assert_eq!(values.len(), expected.len());
+            for (value, expected) in values.iter().zip(expected) {
+                assert!((value - expected).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn test_log_tiny_values() {
+        let mut cursor = Cursor::new(Vec::new());
+        let tiny = [1e-8, -2.5e-8, 0.0, 3e-12];
+
+        let mut writer = LDWriter::new(&mut cursor, sample_header());
+        writer.log("Tiny", "A", 10, &tiny).unwrap();
+
+        let mut reader = LDReader::new(&mut cursor);
+        let channels = reader.read_channels().unwrap();
+        assert_eq!(channels[0].datatype, Datatype::F32);
+
+        let values = reader.channel_data_decoded(&channels[0]).unwrap();
+        for (value, expected) in values.iter().zip(tiny) {
+            assert!((value - expected).abs() <= expected.abs() * f32::EPSILON as f64);
+        }
+    }
+
+    #[test]
+    fn test_copy_from() {
+        let b = ChannelMetadata {
+            datatype: Datatype::I32,
+            ..sample_channel("B")
+        };
+        let c = ChannelMetadata {
+            datatype: Datatype::F32,
+            ..sample_channel("C")
+        };
+
+        let mut source = Cursor::new(Vec::new());
+        LDWriter::new(&mut source, sample_header())
+            .with_channel(sample_channel("A"), vec![Sample::I16(1)])
+            .with_channel(b, vec![Sample::I32(2)])
+            .with_channel(c, vec![Sample::F32(3.0)])
+            .write()
+            .unwrap();
+
+        let mut copy = Cursor::new(Vec::new());
+        let mut reader = LDReader::new(&mut source);
+        let mut writer = LDWriter::new(&mut copy, sample_header());
+        writer.copy_from(&mut reader, |c| c.name != "B").unwrap();
+
+        let mut reader = LDReader::new(&mut copy);
+        let channels = reader.read_channels().unwrap();
+        assert_eq!(channels.len(), 2);
+        assert_eq!(channels[0].name, "A");
+        assert_eq!(channels[1].name, "C");
+        assert_eq!(
+            reader.channel_data(&channels[0]).unwrap(),
+            vec![Sample::I16(1)]
+        );
+        assert_eq!(
+            reader.channel_data(&channels[1]).unwrap(),
+            vec![Sample::F32(3.0)]
+        );
+    }
+
+    /// Copying Sample1.ld should reproduce the raw ranges stored in its metadata trailers
+    #[test]
+    fn test_copy_raw_range() {
+        let original = fs::read("./samples/Sample1.ld").unwrap();
+        let mut source = Cursor::new(original.clone());
+        let mut reader = LDReader::new(&mut source);
+        let header = reader.read_header().unwrap();
+        let channels = reader.read_channels().unwrap();
+
+        let mut copy = Cursor::new(Vec::new());
+        LDWriter::new(&mut copy, header)
+            .copy_from(&mut reader, |_| true)
+            .unwrap();
+        let copy = copy.into_inner();
+
+        let trailer = |bytes: &[u8], meta_addr: u32| {
+            let addr = (meta_addr + ChannelMetadata::FIXED_SIZE) as usize;
+            bytes[addr..addr + 8].to_vec()
+        };
+
+        let mut copied = Cursor::new(&copy);
+        let copied_channels = LDReader::new(&mut copied).read_channels().unwrap();
+        assert_eq!(copied_channels.len(), channels.len());
+
+        let mut original_addr = 0x3448;
+        let mut copied_addr = 0x3448;
+        for (a, b) in channels.iter().zip(copied_channels.iter()) {
+            assert_eq!(
+                trailer(&original, original_addr),
+                trailer(&copy, copied_addr),
+                "{}",
+                a.name
+            );
+            original_addr = a.next_addr;
+            copied_addr = b.next_addr;
+        }
+    }
+
+    #[test]
+    fn test_pad_to() {
+        let channel = ChannelMetadata {
+            prev_addr: 0,
+            next_addr: 0,
+            data_addr: 0,
+            data_count: 0,
+            datatype: Datatype::I16,
+            sample_rate: 10,
+            offset: 0,
+            mul: 1,
+            scale: 1,
+            dec_places: 0,
+            name: "Air Temp Inlet".to_string(),
+            short_name: "Air Tem".to_string(),
+            unit: "C".to_string(),
+        };
+
+        let mut cursor = Cursor::new(Vec::new());
+        LDWriter::new(&mut cursor, sample_header())
+            .with_channel(channel.clone(), vec![Sample::I16(1); 4])
+            .pad_to(32 * 1024)
+            .write()
+            .unwrap();
+
+        let bytes = cursor.into_inner();
+        assert_eq!(bytes.len(), 32 * 1024);
+        assert!(bytes[13384 + 124 + 8..].iter().all(|b| *b == 0));
+
+        let mut cursor = Cursor::new(Vec::new());
+        let result = LDWriter::new(&mut cursor, sample_header())
+            .with_channel(channel.clone(), vec![Sample::I16(1); 4])
+            .pad_to(1024)
+            .write();
+        assert!(matches!(
+            result,
+            Err(I2Error::PaddingTooSmall {
+                size: 1024,
+                content_size: 13516
+            })
+        ));
+    }
+
+    #[test]
+    fn test_device_flags_round_trip() {
+        let header = Header {
+            device_flags: 0x00C1,
+            ..sample_header()
+        };
+
+        let mut cursor = Cursor::new(Vec::new());
+        LDWriter::new(&mut cursor, header).write().unwrap();
+        let read = LDReader::new(&mut cursor).read_header().unwrap();
+        assert_eq!(read.device_flags, 0x00C1);
+
+        let mut copy = Cursor::new(Vec::new());
+        LDWriter::new(&mut copy, read).write().unwrap();
+        let read = LDReader::new(&mut copy).read_header().unwrap();
+        assert_eq!(read.device_flags, 0x00C1);
+    }
+
+    #[test]
+    fn test_sample_datatype_mismatch() {
+        let channel = ChannelMetadata {
+            prev_addr: 0,
+            next_addr: 0,
+            data_addr: 0,
+            data_count: 0,
+            datatype: Datatype::I16,
+            sample_rate: 10,
+            offset: 0,
+            mul: 1,
+            scale: 1,
+            dec_places: 0,
+            name: "Air Temp Inlet".to_string(),
+            short_name: "Air Tem".to_string(),
+            unit: "C".to_string(),
+        };
+
+        let mut cursor = Cursor::new(Vec::new());
+        let result = LDWriter::new(&mut cursor, sample_header())
+            .with_channel(channel.clone(), vec![Sample::I16(1), Sample::F32(2.0)])
+            .write();
+        assert!(matches!(
+            result,
+            Err(I2Error::SampleDatatypeMismatch {
+                expected: Datatype::I16,
+                found: Datatype::F32,
+                ..
+            })
+        ));
+
+        let mut cursor = Cursor::new(Vec::new());
+        let mut writer = LDWriter::new(&mut cursor, sample_header());
+        let mut handle = writer.begin_channel(channel).unwrap();
+        assert!(matches!(
+            handle.write_block(&[Sample::I32(1)]),
+            Err(I2Error::SampleDatatypeMismatch {
+                expected: Datatype::I16,
+                found: Datatype::I32,
+                ..
+            })
+        ));
+    }
+    #[test]
+    fn test_write_with_layout() {
+        let mut cursor = Cursor::new(Vec::new());
+        let layout = LDWriter::new(&mut cursor, sample_header())
+            .with_channel(sample_channel("A"), vec![Sample::I16(1); 3])
+            .with_channel(sample_channel("B"), vec![Sample::I16(2); 5])
+            .write_with_layout()
+            .unwrap();
+
+        let mut reader = LDReader::new(&mut cursor);
+        let channels = reader.read_channels().unwrap();
+        assert_eq!(layout.channels.len(), channels.len());
+        assert_eq!(layout.channels[0].meta_addr, 0x3448);
+        assert_eq!(layout.channels[1].meta_addr, channels[0].next_addr);
+        for (layout, channel) in layout.channels.iter().zip(channels.iter()) {
+            assert_eq!(layout.name, channel.name);
+            assert_eq!(layout.data_addr, channel.data_addr);
+            assert_eq!(layout.data_size, channel.data_size());
+        }
+
+        let mut cursor = Cursor::new(Vec::new());
+        let mut writer = LDWriter::new(&mut cursor, sample_header());
+        writer.log("C", "", 10, &[1.0, 2.0]).unwrap();
+        writer.log("D", "", 10, &[3.0]).unwrap();
+        let layout = writer.layout().clone();
+
+        let mut reader = LDReader::new(&mut cursor);
+        let channels = reader.read_channels().unwrap();
+        assert_eq!(layout.channels[0].meta_addr, 0x3448);
+        assert_eq!(layout.channels[1].meta_addr, channels[0].next_addr);
+        assert_eq!(layout.channels[1].data_addr, channels[1].data_addr);
+    }
+
+    #[test]
+    fn test_extensions_round_trip() {
+        let channel = ChannelMetadata {
+            prev_addr: 0,
+            next_addr: 0,
+            data_addr: 0,
+            data_count: 0,
+            datatype: Datatype::I16,
+            sample_rate: 10,
+            offset: 0,
+            mul: 1,
+            scale: 1,
+            dec_places: 0,
+            name: "Air Temp Inlet".to_string(),
+            short_name: "Air Tem".to_string(),
+            unit: "C".to_string(),
+        };
+
+        let mut cursor = Cursor::new(Vec::new());
+        let mut writer = LDWriter::new(&mut cursor, sample_header())
+            .with_channel(channel.clone(), vec![Sample::I16(1); 3]);
+        writer.set_extension("tool", "lap-splitter");
+        writer.set_extension("setup", "v1");
+        writer.set_extension("setup", "wet, soft dampers");
+        writer.write().unwrap();
+
+        let mut reader = LDReader::new(&mut cursor);
+        assert_eq!(
+            reader.extensions().unwrap(),
+            [
+                ("tool".to_string(), "lap-splitter".to_string()),
+                ("setup".to_string(), "wet, soft dampers".to_string()),
+            ]
+        );
+        let channels = reader.read_channels().unwrap();
+        assert_eq!(
+            reader.channel_data(&channels[0]).unwrap(),
+            vec![Sample::I16(1); 3]
+        );
+
+        // Written by the block API, and then overwritten by the next channel
+        let mut cursor = Cursor::new(Vec::new());
+        let mut writer = LDWriter::new(&mut cursor, sample_header());
+        writer.set_extension("tool", "logger");
+        writer.log("A", "", 10, &[1.0, 2.0]).unwrap();
+        writer.log("B", "", 10, &[3.0]).unwrap();
+
+        let mut reader = LDReader::new(&mut cursor);
+        assert_eq!(
+            reader.extensions().unwrap(),
+            [("tool".to_string(), "logger".to_string())]
+        );
+        let channels = reader.read_channels().unwrap();
+        assert_eq!(reader.channel_data(&channels[1]).unwrap().len(), 1);
+
+        let mut cursor = Cursor::new(Vec::new());
+        LDWriter::new(&mut cursor, sample_header())
+            .with_channel(channel, vec![Sample::I16(1); 3])
+            .write()
+            .unwrap();
+        assert!(LDReader::new(&mut cursor).extensions().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_extensions_corrupt_lengths() {
+        let mut cursor = Cursor::new(Vec::new());
+        let mut writer = LDWriter::new(&mut cursor, sample_header());
+        writer.set_extension("tool", "logger");
+        writer.log("A", "", 10, &[1.0, 2.0]).unwrap();
+
+        let bytes = cursor.into_inner();
+        let marker = bytes
+            .windows(EXTENSION_MARKER.len())
+            .position(|w| w == EXTENSION_MARKER)
+            .unwrap();
+        let count_addr = marker + EXTENSION_MARKER.len();
+
+        // Huge entry count, and then a huge key length
+        for addr in [count_addr, count_addr + 4] {
+            let mut corrupt = bytes.clone();
+            corrupt[addr..addr + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+            match LDReader::new(&mut Cursor::new(corrupt)).extensions() {
+                Err(I2Error::UnexpectedEof {
+                    while_reading,
+                    offset,
+                }) => {
+                    assert_eq!(while_reading, "extension block");
+                    assert_eq!(offset, addr as u64);
+                }
+                other => panic!("expected an UnexpectedEof error, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_f16_round_trip() {
+        let channel = ChannelMetadata {
+            prev_addr: 0,
+            next_addr: 0,
+            data_addr: 0,
+            data_count: 0,
+            datatype: Datatype::F16,
+            sample_rate: 1,
+            offset: 0,
+            mul: 1,
+            scale: 1,
+            dec_places: 0,
+            name: "Fuel Level".to_string(),
+            short_name: "Fuel".to_string(),
+            unit: "l".to_string(),
+        };
+        let values = [0.1f32, 21.37, -1000.5, 2.71, 60000.0];
+
+        let mut cursor = Cursor::new(Vec::new());
+        LDWriter::new(&mut cursor, sample_header())
+            .with_channel(
+                channel.clone(),
+                values.iter().map(|v| Sample::F16(*v)).collect(),
+            )
+            .write()
+            .unwrap();
+
+        let mut reader = LDReader::new(&mut cursor);
+        let channels = reader.read_channels().unwrap();
+        assert_eq!(channels[0].datatype, Datatype::F16);
+        assert_eq!(channels[0].data_size(), 10);
+
+        let data = reader.channel_data(&channels[0]).unwrap();
+        for (sample, value) in data.iter().zip(values.iter()) {
+            let read = sample.decode_f64(&channels[0]);
+            // Halves have 11 bits of precision
+            assert!((read - *value as f64).abs() <= value.abs() as f64 * 2.0f64.powi(-11));
+        }
+
+        let mut cursor = Cursor::new(Vec::new());
+        let mut writer = LDWriter::new(&mut cursor, sample_header());
+        let mut handle = writer.begin_channel(channel).unwrap();
+        handle.write_block_f64(&[12.5, -0.25]).unwrap();
+        assert!(matches!(
+            handle.write_block_f64(&[70000.0]),
+            Err(I2Error::SampleOverflow { .. })
+        ));
+        handle.finish().unwrap();
+
+        let mut reader = LDReader::new(&mut cursor);
+        let channels = reader.read_channels().unwrap();
+        assert_eq!(
+            reader.channel_data(&channels[0]).unwrap(),
+            vec![Sample::F16(12.5), Sample::F16(-0.25)]
+        );
+    }
+
+    /// Everything written should be read back the same, apart from the addresses and counts
+    /// that the writer fills in
+    #[test]
+    fn test_negative_offset_round_trip() {
+        let channel = ChannelMetadata {
+            prev_addr: 0,
+            next_addr: 0,
+            data_addr: 0,
+            data_count: 0,
+            datatype: Datatype::I16,
+            sample_rate: 1,
+            offset: -40,
+            mul: 1,
+            scale: 1,
+            dec_places: 1,
+            name: "Oil Temp".to_string(),
+            short_name: "OilTemp".to_string(),
+            unit: "C".to_string(),
+        };
+
+        let mut cursor = Cursor::new(Vec::new());
+        LDWriter::new(&mut cursor, sample_header())
+            .with_channel(channel, vec![Sample::I16(0), Sample::I16(1305)])
+            .write()
+            .unwrap();
+
+        // The offset is stored as a signed 16 bit value
+        let meta_addr = 0x3448 + 24;
+        assert_eq!(cursor.get_ref()[meta_addr..meta_addr + 2], [0xD8, 0xFF]);
+
+        let mut reader = LDReader::new(&mut cursor);
+        let channels = reader.read_channels().unwrap();
+        assert_eq!(channels[0].offset, -40);
+        let values: Vec<f64> = reader
+            .channel_data(&channels[0])
+            .unwrap()
+            .iter()
+            .map(|s| s.decode_rounded(&channels[0]))
+            .collect();
+        assert_eq!(values, [-40.0, 90.5]);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let oil_pres = ChannelMetadata {
+            prev_addr: 0,
+            next_addr: 0,
+            data_addr: 0,
+            data_count: 0,
+            datatype: Datatype::I16,
+            sample_rate: 10,
+            offset: 0,
+            mul: 1,
+            scale: 1,
+            dec_places: 2,
+            name: "Oil Pres".to_string(),
+            short_name: "OilP".to_string(),
+            unit: "bar".to_string(),
+        };
+        let oil_pres_samples: Vec<Sample> = (0..25).map(|i| Sample::I16(i * 7 - 40)).collect();
+
+        let lambda = ChannelMetadata {
+            datatype: Datatype::F32,
+            sample_rate: 50,
+            dec_places: 0,
+            name: "Lambda".to_string(),
+            short_name: "Lam".to_string(),
+            unit: "".to_string(),
+            ..oil_pres.clone()
+        };
+        let lambda_samples: Vec<Sample> = (0..125)
+            .map(|i| Sample::F32(0.8 + i as f32 / 1000.0))
+            .collect();
+
+        let odometer = ChannelMetadata {
+            datatype: Datatype::I32,
+            sample_rate: 1,
+            mul: 2,
+            scale: 10,
+            dec_places: 1,
+            name: "Odometer".to_string(),
+            short_name: "Odo".to_string(),
+            unit: "km".to_string(),
+            ..oil_pres.clone()
+        };
+        let odometer_samples = vec![Sample::I32(100_000), Sample::I32(100_250)];
+
+        let mut cursor = Cursor::new(Vec::new());
+        LDWriter::new(&mut cursor, sample_header())
+            .with_channel(oil_pres.clone(), oil_pres_samples.clone())
+            .with_channel(lambda.clone(), lambda_samples.clone())
+            .with_channel(odometer.clone(), odometer_samples.clone())
+            .write()
+            .unwrap();
+
+        let mut reader = LDReader::new(&mut cursor).with_strict(true);
+        let header = reader.read_header().unwrap();
+        assert_eq!(
+            header,
+            Header {
+                channel_meta_ptr: 0x3448,
+                channel_data_ptr: 0x3448 + 3 * 124,
+                num_channels: 3,
+                ..sample_header()
+            }
+        );
+
+        let channels = reader.read_channels().unwrap();
+        assert_eq!(channels.len(), 3);
+
+        let meta_addrs = [0x3448, 0x3448 + 124, 0x3448 + 2 * 124];
+        let data_addrs = [
+            header.channel_data_ptr,
+            header.channel_data_ptr + 25 * 2,
+            header.channel_data_ptr + 25 * 2 + 125 * 4,
+        ];
+        let written = [
+            (oil_pres, oil_pres_samples),
+            (lambda, lambda_samples),
+            (odometer, odometer_samples),
+        ];
+        for (i, (read, (channel, samples))) in channels.iter().zip(written).enumerate() {
+            let expected = ChannelMetadata {
+                prev_addr: if i == 0 { 0 } else { meta_addrs[i - 1] },
+                next_addr: meta_addrs.get(i + 1).copied().unwrap_or(0),
+                data_addr: data_addrs[i],
+                data_count: samples.len() as u32,
+                ..channel
+            };
+            assert_eq!(*read, expected);
+            assert_eq!(reader.channel_data(read).unwrap(), samples);
+        }
+    }
+
+    #[test]
+    fn test_append_channel() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut cursor = Cursor::new(bytes);
+
+        let channel = ChannelMetadata {
+            prev_addr: 0,
+            next_addr: 0,
+            data_addr: 0,
+            data_count: 0,
+            datatype: Datatype::I16,
+            sample_rate: 20,
+            offset: 0,
+            mul: 1,
+            scale: 1,
+            dec_places: 1,
+            name: "Wheel Slip".to_string(),
+            short_name: "Slip".to_string(),
+            unit: "%".to_string(),
+        };
+        let samples = vec![Sample::I16(12), Sample::I16(-3), Sample::I16(40)];
+        LDWriter::append_channel(&mut cursor, &channel, &samples).unwrap();
+
+        let mut reader = LDReader::new(&mut cursor).with_strict(true);
+        let channels = reader.read_channels().unwrap();
+        assert_eq!(channels.len(), 79);
+        assert_eq!(reader.read_header().unwrap().num_channels, 79);
+        assert_eq!(channels[77].name, "Steered Angle");
+        assert_eq!(channels[78].name, "Wheel Slip");
+        assert_eq!(channels[78].prev_addr, channels[76].next_addr);
+        assert_eq!(reader.channel_data(&channels[78]).unwrap(), samples);
+        assert_eq!(
+            reader.channel_data(&channels[0]).unwrap()[0],
+            Sample::I16(199)
+        );
+    }
+
+    #[test]
+    fn test_edit_channels() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let original_len = bytes.len();
+        let mut cursor = Cursor::new(bytes);
+
+        let mut reader = LDReader::new(&mut cursor);
+        let original = reader.read_channels().unwrap();
+        let original_data = reader.channel_data(&original[1]).unwrap();
+
+        LDWriter::edit_channels(&mut cursor, |channel| {
+            if channel.name == "Brake Temp FL" {
+                channel.name = "Brake Temp Front Left".to_string();
+                channel.short_name = "BrkTFL".to_string();
+                channel.unit = "degC".to_string();
+                // Ignored, it would break the data section
+                channel.data_count = 1;
+            }
+        })
+        .unwrap();
+        assert_eq!(cursor.get_ref().len(), original_len);
+
+        let mut reader = LDReader::new(&mut cursor).with_strict(true);
+        let channels = reader.read_channels().unwrap();
+        assert_eq!(channels.len(), 78);
+        assert_eq!(channels[1].name, "Brake Temp Front Left");
+        assert_eq!(channels[1].short_name, "BrkTFL");
+        assert_eq!(channels[1].unit, "degC");
+        assert_eq!(channels[1].data_count, original[1].data_count);
+        assert_eq!(reader.channel_data(&channels[1]).unwrap(), original_data);
+        assert_eq!(channels[0], original[0]);
+        assert_eq!(channels[2..], original[2..]);
+    }
+
+    #[test]
+    fn test_edit_channels_dec_places() {
+        let bytes = fs::read("./samples/Sample1.ld").unwrap();
+        let mut cursor = Cursor::new(bytes);
+
+        LDWriter::edit_channels(&mut cursor, |channel| channel.dec_places += 1).unwrap();
+
+        let mut reader = LDReader::new(&mut cursor);
+        let channels = reader.read_channels().unwrap();
+        assert_eq!(channels[0].name, "Air Temp Inlet");
+        let value = reader.channel_data_decoded(&channels[0]).unwrap()[0];
+        assert!((value - 1.99).abs() < 0.000001);
+    }
+
+    #[test]
+    fn test_append_channel_to_empty_file() {
+        let mut cursor = Cursor::new(Vec::new());
+        LDWriter::new(&mut cursor, sample_header()).write().unwrap();
+
+        let channel = ChannelMetadata {
+            prev_addr: 0,
+            next_addr: 0,
+            data_addr: 0,
+            data_count: 0,
+            datatype: Datatype::F32,
+            sample_rate: 10,
+            offset: 0,
+            mul: 1,
+            scale: 1,
+            dec_places: 0,
+            name: "Lambda".to_string(),
+            short_name: "Lam".to_string(),
+            unit: "".to_string(),
+        };
+        LDWriter::append_channel(&mut cursor, &channel, &[Sample::F32(0.9)]).unwrap();
+        let second = ChannelMetadata {
+            name: "Lambda 2".to_string(),
+            ..channel
+        };
+        LDWriter::append_channel(&mut cursor, &second, &[Sample::F32(1.1)]).unwrap();
+
+        let mut reader = LDReader::new(&mut cursor).with_strict(true);
+        let channels = reader.read_channels().unwrap();
+        assert_eq!(channels.len(), 2);
+        assert_eq!(channels[0].name, "Lambda");
+        assert_eq!(channels[1].name, "Lambda 2");
+        assert_eq!(
+            reader.channel_data(&channels[0]).unwrap(),
+            [Sample::F32(0.9)]
+        );
+        assert_eq!(
+            reader.channel_data(&channels[1]).unwrap(),
+            [Sample::F32(1.1)]
+        );
+    }
 }